@@ -1,23 +1,63 @@
+use std::f64::consts::FRAC_PI_2;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
 use std::thread::spawn;
+use std::time::Duration;
 
 use egui::{
-    Context, DragValue, KeyboardShortcut, Modifiers, Vec2, ViewportCommand, Image, Sense,
+    Color32, Context, DragValue, KeyboardShortcut, Modifiers, Vec2, ViewportCommand, Image, Sense,
 };
 use event_file_reader::EventFileReader as Reader;
 use jetty::PseudoJet;
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use nalgebra::{Rotation3, Vector3, Unit};
+use strum::IntoEnumIterator;
 
 const BYTES_PER_RGB_PIXEL: usize = 3;
-const BYTES_PER_RGBA_PIXEL: usize = 4;
+pub(crate) const BYTES_PER_RGBA_PIXEL: usize = 4;
 
-use crate::clustering::{cluster, ClusterSettings};
+// zoom range and speed of the 3D view
+const MIN_3D_SCALE: f64 = 0.1;
+const MAX_3D_SCALE: f64 = 10.;
+const SCROLL_ZOOM_SPEED: f64 = 0.002;
+
+/// File argument for reading events from standard input
+#[cfg(not(target_arch = "wasm32"))]
+const STDIN_ARG: &str = "-";
+#[cfg(not(target_arch = "wasm32"))]
+const STDIN_NAME: &str = "standard input";
+/// Number of bytes read from standard input before detecting the format
+#[cfg(not(target_arch = "wasm32"))]
+const STDIN_SNIFF_LEN: usize = 256;
+/// Interval for progress messages when spooling standard input, in bytes
+#[cfg(not(target_arch = "wasm32"))]
+const STDIN_PROGRESS_BYTES: usize = 1 << 24;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::archive;
+use crate::clustering::{
+    cluster, cluster_sequence, cluster_with_constituents, jet_area_radius,
+    ClusterSequence, ClusterSettings, ClusterSummary, JetDefinition,
+};
 use crate::event::Event;
-use crate::export::export;
-use crate::plotter::{PlotResponse, Plotter};
+use crate::export::{export, BatchExport, Provenance};
+use crate::hepmc3;
+use crate::observables::Observable;
+use crate::particle::{invariant_mass, Particle, ParticleGroup};
+use crate::plotter::{
+    ColourMap, MarkerSizing, Palette, PlotResponse, Plotter, PtScale, Rapidity,
+    Settings, Settings3D, StatusFilter,
+};
+use crate::preset::Preset;
+use crate::stream::{self, EventIndex, EventStream};
 use crate::windows::{
-    ExportDialogue, ImportDialogue, ParticleStyleChoiceWin, YLogPtWin, YPhiWin,
+    BatchExportDialogue, ConventionsWin, DetectorGeometryWin, DetectorWin,
+    DisplayCutsWin, EventInfoWin, EventSummaryWin, ExportDialogue,
+    FindEventWin, ImportDialogue, JetListWin, JetSelectionWin,
+    ParticleStyleChoiceWin, PresetAction, PresetDialogue, PtSpectrumWin,
+    RapidityDistributionWin, ThumbnailWin, YLogPtWin, YPhiWin,
 };
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -27,42 +67,216 @@ use crate::windows::{
 pub struct TemplateApp {
     y_log_pt: YLogPtWin,
     y_phi: YPhiWin,
+    event_info: EventInfoWin,
+    event_summary: EventSummaryWin,
+    find_event: FindEventWin,
+    thumbnails: ThumbnailWin,
+    detector_geometry: DetectorGeometryWin,
+    detector: DetectorWin,
+    display_cuts: DisplayCutsWin,
+    conventions: ConventionsWin,
+    jet_list: JetListWin,
+    pt_spectrum: PtSpectrumWin,
+    rapidity_distribution: RapidityDistributionWin,
     plotter: Plotter,
     clustering: ClusterSettings,
+    observable: Observable,
     #[serde(skip)]
     particle_style_choice_win: ParticleStyleChoiceWin,
     #[serde(skip)]
+    jet_selection_win: JetSelectionWin,
+    #[serde(skip)]
     open_file_win: ImportDialogue,
     #[serde(skip)]
     export_win: ExportDialogue,
     #[serde(skip)]
+    batch_export_win: BatchExportDialogue,
+    #[serde(skip)]
+    preset_win: PresetDialogue,
+    /// Cancellation flag of the running batch export
+    #[serde(skip)]
+    batch_cancel: Option<Arc<AtomicBool>>,
+    #[serde(skip)]
     events: Vec<Event>,
+    /// Read large files on demand instead of loading all events
+    stream_files: bool,
+    /// Source of `events` in streaming mode, which then only hold a
+    /// window around the current event
+    #[serde(skip)]
+    stream: Option<EventStream>,
     #[serde(skip)]
     jets: Vec<PseudoJet>,
     #[serde(skip)]
+    sequence: ClusterSequence,
+    #[serde(skip)]
+    sequence_key: Option<(usize, usize, JetDefinition)>,
+    /// Inputs that produced the current `jets` outside of sequence mode
+    #[serde(skip)]
+    cluster_key: Option<(usize, usize, JetDefinition, bool)>,
+    #[serde(skip)]
     event_idx: usize,
+    /// Index of the selected particle, ordered by decreasing pT
+    #[serde(skip)]
+    selected_particle: Option<usize>,
+    #[serde(skip)]
+    particles_by_pt: ParticlesByPt,
+    /// Up to two particles for the invariant mass readout
+    #[serde(skip)]
+    pair: Vec<Particle>,
     #[serde(skip)]
     bottom_panel: BottomPanelData,
+    animation: Animation,
     #[serde(skip)]
     msg: String,
     #[serde(skip)]
-    s_file: Option<Sender<String>>, // have to use Option to derive Default
+    s_job: Option<Sender<Job>>, // have to use Option to derive Default
     #[serde(skip)]
     r_ev: Option<Receiver<Event>>, // have to use Option to derive Default
     #[serde(skip)]
     r_msg: Option<Receiver<String>>, // have to use Option to derive Default
+    #[serde(skip)]
+    r_index: Option<Receiver<EventIndex>>, // have to use Option to derive Default
 
     #[serde(skip)]
     plot_3d: Option<egui::TextureHandle>,
+    /// Last error from drawing the 3D plot, reported only once
+    #[serde(skip)]
+    plot_3d_error: Option<String>,
+    #[serde(skip)]
+    confirm_reset: bool,
+    compare: CompareView,
+    #[serde(skip)]
+    diagnostics: Diagnostics,
+    /// Size of the main window, restored in the next session
+    window_size: Option<Vec2>,
+}
+
+/// Performance information shown on top of the central panel
+#[derive(Default)]
+struct Diagnostics {
+    enabled: bool,
+    render_3d_time: Option<std::time::Duration>,
+}
+
+/// Side-by-side view of two events
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct CompareView {
+    enabled: bool,
+    sync: bool,
+    #[serde(skip)]
+    other_idx: usize,
+    /// Jets in the other event
+    #[serde(skip)]
+    jets: Vec<PseudoJet>,
+    /// Event and clustering settings for `jets`
+    #[serde(skip)]
+    jets_key: Option<(usize, usize, JetDefinition, bool)>,
+}
+
+impl CompareView {
+    /// Jets in the other event, only reclustered if something changed
+    fn other_jets(
+        &mut self,
+        events: &[Event],
+        clustering: &ClusterSettings,
+    ) -> &[PseudoJet] {
+        let key = (
+            self.other_idx,
+            events.len(),
+            clustering.jet_def,
+            clustering.clustering_enabled,
+        );
+        if self.jets_key != Some(key) {
+            self.jets = match events.get(self.other_idx) {
+                Some(event) if clustering.clustering_enabled => {
+                    cluster(event, &clustering.jet_def)
+                }
+                _ => Vec::new(),
+            };
+            self.jets_key = Some(key);
+        }
+        &self.jets
+    }
+}
+
+/// Visible particles of the current event, ordered by decreasing pT
+#[derive(Default)]
+struct ParticlesByPt {
+    particles: Vec<Particle>,
+    /// Event and number of loaded events for `particles`
+    key: Option<(usize, usize)>,
+    /// Settings with the cuts applied to `particles`
+    settings: Settings,
+}
+
+impl ParticlesByPt {
+    /// Sorted particles, only updated if the event or settings changed
+    fn get(
+        &mut self,
+        events: &[Event],
+        event_idx: usize,
+        settings: &Settings,
+    ) -> &[Particle] {
+        let key = Some((event_idx, events.len()));
+        if self.key != key || self.settings != *settings {
+            self.particles = match events.get(event_idx) {
+                Some(event) => {
+                    let mut particles = settings.visible_particles(event);
+                    particles.sort_by(|a, b| b.pt.total_cmp(&a.pt));
+                    particles
+                }
+                None => Vec::new(),
+            };
+            self.key = key;
+            self.settings = settings.clone();
+        }
+        &self.particles
+    }
+}
+
+/// Tasks for the background thread
+enum Job {
+    Load(String),
+    /// Build an index for streaming events from a file
+    Index(String),
+    Export(Box<BatchExport>),
 }
 
 struct BottomPanelData {
-    space: f32,
+    /// Width of the navigation controls
+    controls_width: f32,
 }
 
 impl Default for BottomPanelData {
     fn default() -> Self {
-        Self { space: 0. }
+        Self { controls_width: 0. }
+    }
+}
+
+/// Automatic stepping through the events
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Animation {
+    #[serde(skip)]
+    playing: bool,
+    /// Events per second
+    rate: f64,
+    /// Continue with the first event after the last one
+    looping: bool,
+    /// Event index and time of the last step
+    #[serde(skip)]
+    last_step: Option<(usize, f64)>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            rate: 2.,
+            looping: false,
+            last_step: None,
+        }
     }
 }
 
@@ -120,55 +334,34 @@ impl TemplateApp {
         } else {
             Self::default()
         };
+        if let Some(size) = res.window_size {
+            context.send_viewport_cmd(ViewportCommand::InnerSize(size));
+        }
 
-        let (s_file, r_file) = channel();
+        let (s_job, r_job) = channel();
         let (s_ev, r_ev) = channel();
         let (s_msg, r_msg) = channel();
+        let (s_index, r_index) = channel();
         spawn(move || {
-            while let Ok(file) = r_file.recv() {
-                if s_msg.send(format!("Loading events from {file}")).is_err() {
-                    break;
-                }
-                let reader = match Reader::new(&file) {
-                    Ok(reader) => reader,
-                    Err(err) => {
-                        if s_msg
-                            .send(format!("Failed to read from {file}: {err}"))
-                            .is_err()
-                        {
-                            break;
-                        } else {
-                            continue;
-                        }
-                    }
+            while let Ok(job) = r_job.recv() {
+                let running = match job {
+                    Job::Load(file) => load_file(&file, &s_ev, &s_msg),
+                    Job::Index(file) => index_file(&file, &s_index, &s_msg),
+                    Job::Export(batch) => batch.run(&s_msg),
                 };
-                for event in reader {
-                    match event {
-                        Ok(event) => {
-                            if s_ev.send(event.into()).is_err() {
-                                break;
-                            }
-                        }
-                        Err(err) => {
-                            let _ = s_msg.send(format!(
-                                "Failed to read from {file}: {err}"
-                            ));
-                        }
-                    }
-                }
-                if s_msg.send(String::new()).is_err() {
+                if !running {
                     break;
                 }
             }
         });
-        for file in std::env::args().skip(1) {
-            if s_file.send(file).is_err() {
-                break;
-            }
-        }
         res.r_msg = Some(r_msg);
         res.r_ev = Some(r_ev);
-        res.s_file = Some(s_file);
+        res.r_index = Some(r_index);
+        res.s_job = Some(s_job);
+        let args = Vec::from_iter(std::env::args().skip(1));
+        if !args.is_empty() {
+            res.load_files(args.into_iter().map(PathBuf::from));
+        }
         res
     }
 
@@ -184,6 +377,10 @@ impl TemplateApp {
                 if ui.button("Open (Ctrl+O)").clicked() {
                     self.open_file_win.open();
                 }
+                if ui.button("Export all events").clicked() {
+                    self.batch_export_win.is_open = true;
+                    ui.close_menu();
+                }
                 // if ui.button("Quit (Ctrl+Q)").clicked() {
                 if ui.button("Quit").clicked() {
                     ctx.send_viewport_cmd(ViewportCommand::Close);
@@ -193,6 +390,246 @@ impl TemplateApp {
                 if ui.button("Jet clustering").clicked() {
                     self.clustering.is_open = true;
                 }
+                if ui.button("Detector geometry").clicked() {
+                    self.detector_geometry.is_open = true;
+                }
+                if ui.button("Display cuts").clicked() {
+                    self.display_cuts.is_open = true;
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.checkbox(
+                    &mut self.stream_files,
+                    "Stream large uncompressed HepMC3 files",
+                )
+                .on_hover_text(
+                    "Only keep events around the current one in memory. \
+                     Takes effect when the next file is opened.",
+                );
+                let has_thresholds = !self.plotter.settings.min_pt.is_empty();
+                let clear_thresholds = ui.add_enabled(
+                    has_thresholds,
+                    egui::Button::new("Show all soft particles"),
+                );
+                if clear_thresholds.clicked() {
+                    self.plotter.settings.min_pt.clear();
+                    ui.close_menu();
+                }
+                let has_styles = !self.plotter.settings.particles.is_empty();
+                let reset_styles = ui.add_enabled(
+                    has_styles,
+                    egui::Button::new("Reset all particle styles"),
+                );
+                if reset_styles.clicked() {
+                    self.plotter.settings.particles.clear();
+                    ui.close_menu();
+                }
+                ui.menu_button("Style particle group", |ui| {
+                    for group in ParticleGroup::iter() {
+                        if ui.button(group.to_string()).clicked() {
+                            self.particle_style_choice_win
+                                .select(group.members(), false);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    if ui.button("Save style preset").clicked() {
+                        self.preset_win.open(PresetAction::Save);
+                        ui.close_menu();
+                    }
+                    if ui.button("Load style preset").clicked() {
+                        self.preset_win.open(PresetAction::Load);
+                        ui.close_menu();
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut self.plotter.max_phi_images)
+                            .clamp_range(1..=64),
+                    );
+                    ui.label("Maximum number of periodic φ images");
+                });
+                let status_filter = &mut self.plotter.settings.status_filter;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("Status filter")
+                        .selected_text(status_filter.to_string())
+                        .show_ui(ui, |ui| {
+                            for filter in StatusFilter::iter() {
+                                ui.selectable_value(
+                                    status_filter,
+                                    filter,
+                                    filter.to_string(),
+                                );
+                            }
+                        });
+                    ui.label("Shown particles");
+                });
+                ui.checkbox(
+                    &mut self.plotter.declutter,
+                    "Combine overlapping markers",
+                );
+                ui.checkbox(
+                    &mut self.plotter.show_incoming,
+                    "Show incoming particles",
+                );
+                ui.checkbox(
+                    &mut self.plotter.label_jets,
+                    "Label jets by transverse momentum",
+                );
+                ui.checkbox(
+                    &mut self.plotter.show_beam_axis,
+                    "Show beam axis and interaction point",
+                );
+                ui.checkbox(
+                    &mut self.plotter.settings_3d.gradient,
+                    "Colour gradient along 3D momentum lines",
+                );
+                let marker_size = &mut self.plotter.settings.marker_size;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("Marker sizing")
+                        .selected_text(marker_size.sizing.to_string())
+                        .show_ui(ui, |ui| {
+                            for sizing in MarkerSizing::iter() {
+                                ui.selectable_value(
+                                    &mut marker_size.sizing,
+                                    sizing,
+                                    sizing.to_string(),
+                                );
+                            }
+                        });
+                    ui.label("Marker size");
+                });
+                if marker_size.sizing != MarkerSizing::Constant {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut marker_size.scale)
+                                .speed(0.001)
+                                .clamp_range(0.0..=f32::MAX),
+                        );
+                        ui.label("Marker size per GeV");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut marker_size.min)
+                                .clamp_range(0.0..=marker_size.max),
+                        );
+                        ui.label("–");
+                        ui.add(
+                            DragValue::new(&mut marker_size.max)
+                                .clamp_range(marker_size.min..=f32::MAX),
+                        );
+                        ui.label("Marker size range");
+                    });
+                }
+                let settings = &mut self.plotter.settings;
+                ui.horizontal(|ui| {
+                    let mut palette = settings.palette;
+                    egui::ComboBox::from_id_source("Palette")
+                        .selected_text(palette.to_string())
+                        .show_ui(ui, |ui| {
+                            for choice in Palette::iter() {
+                                ui.selectable_value(
+                                    &mut palette,
+                                    choice,
+                                    choice.to_string(),
+                                );
+                            }
+                        });
+                    if palette != settings.palette {
+                        settings.set_palette(palette);
+                    }
+                    ui.label("Default particle colours");
+                });
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgba(&mut settings.background);
+                    ui.label("Plot background");
+                });
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgba(&mut settings.frame);
+                    ui.label("Plot frame");
+                });
+                ui.checkbox(
+                    &mut settings.per_jet_colours,
+                    "Different colour for each jet",
+                );
+                let pt_colours = &mut self.plotter.settings.pt_colours;
+                ui.checkbox(
+                    &mut pt_colours.enabled,
+                    "Colour particles by transverse momentum",
+                );
+                ui.add_enabled_ui(pt_colours.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("Colour map")
+                            .selected_text(pt_colours.map.to_string())
+                            .show_ui(ui, |ui| {
+                                for map in ColourMap::iter() {
+                                    ui.selectable_value(
+                                        &mut pt_colours.map,
+                                        map,
+                                        map.to_string(),
+                                    );
+                                }
+                            });
+                        ui.label("Colour map");
+                    });
+                    ui.horizontal(|ui| {
+                        let [min, max] = &mut pt_colours.pt_range;
+                        ui.add(
+                            DragValue::new(min).clamp_range(1e-3..=f64::MAX),
+                        );
+                        ui.label("–");
+                        ui.add(
+                            DragValue::new(max).clamp_range(1e-3..=f64::MAX),
+                        );
+                        ui.label("Transverse momentum range");
+                    });
+                });
+                let preview = &mut self.plotter.merge_preview;
+                ui.checkbox(&mut preview.enabled, "Show candidate merges");
+                ui.add_enabled_ui(preview.enabled, |ui| {
+                    ui.horizontal(|ui| {
+                        let mut custom = preview.max_delta_r.is_some();
+                        ui.checkbox(&mut custom, "Maximum ΔR");
+                        if custom {
+                            let max_delta_r = preview
+                                .max_delta_r
+                                .get_or_insert(self.plotter.r_jet);
+                            ui.add(
+                                DragValue::new(max_delta_r)
+                                    .speed(0.01)
+                                    .clamp_range(0.0..=f64::MAX),
+                            );
+                        } else {
+                            preview.max_delta_r = None;
+                            let r_jet = self.plotter.r_jet;
+                            ui.label(format!("{r_jet:.2} (jet radius)"));
+                        }
+                    });
+                });
+                ui.separator();
+                if ui
+                    .button("Reset view (keep styles)")
+                    .on_hover_text(
+                        "Reset zoom, 3D orientation, and pT thresholds",
+                    )
+                    .clicked()
+                {
+                    self.reset_view(ctx);
+                    ui.close_menu();
+                }
+                if ui
+                    .button("Reset styles (keep view)")
+                    .on_hover_text("Reset particle and jet colours and shapes")
+                    .clicked()
+                {
+                    self.reset_styles(ctx);
+                    ui.close_menu();
+                }
+                if ui.button("Reset all settings").clicked() {
+                    self.confirm_reset = true;
+                    ui.close_menu();
+                }
             });
             ui.menu_button("Windows", |ui| {
                 ui.checkbox(
@@ -203,6 +640,19 @@ impl TemplateApp {
                     &mut self.y_phi.is_open,
                     "Azimuthal angle over rapidity",
                 );
+                ui.checkbox(&mut self.event_info.is_open, "Event information");
+                ui.checkbox(&mut self.event_summary.is_open, "Event summary");
+                ui.checkbox(&mut self.thumbnails.is_open, "Event overview");
+                ui.checkbox(&mut self.compare.enabled, "Compare two events");
+                ui.checkbox(&mut self.jet_list.is_open, "Jets");
+                ui.checkbox(&mut self.pt_spectrum.is_open, "pT spectrum");
+                ui.checkbox(
+                    &mut self.rapidity_distribution.is_open,
+                    "Rapidity distribution",
+                );
+                ui.checkbox(&mut self.detector.is_open, "Transverse view");
+                ui.checkbox(&mut self.conventions.is_open, "Conventions");
+                ui.checkbox(&mut self.diagnostics.enabled, "Diagnostics (F12)");
             });
             egui::global_dark_light_mode_switch(ui)
         });
@@ -211,56 +661,317 @@ impl TemplateApp {
     fn draw_bottom_panel(&mut self, ctx: &Context) {
         eframe::egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.add_space(self.bottom_panel.space);
-                // TODO: use black arrows, but the rightwards one is missing in DejaVu
-                let back_button =
-                    ui.add_enabled(self.event_idx > 0, egui::Button::new("⇦"));
-                if back_button.clicked() {
-                    self.event_idx -= 1;
-                }
+                // centre the controls, using their width in the last frame
+                let width = self.bottom_panel.controls_width;
+                ui.add_space(((ui.available_width() - width) / 2.).max(0.));
+                let controls = ui.horizontal(|ui| {
+                    // TODO: use black arrows, but the rightwards one is missing in DejaVu
+                    let back_button = ui.add_enabled(
+                        self.event_idx > 0,
+                        egui::Button::new("⇦"),
+                    );
+                    if back_button.clicked() {
+                        self.event_idx -= 1;
+                    }
 
-                let mut ev_nr = self.event_idx + 1;
-                ui.add(
-                    DragValue::new(&mut ev_nr)
-                        .clamp_range(1..=self.events.len())
-                        .suffix(format!("/{}", self.events.len())),
+                    let (offset, nevents) = match &self.stream {
+                        Some(stream) => (stream.start(), stream.len()),
+                        None => (0, self.events.len()),
+                    };
+                    let mut ev_nr = offset + self.event_idx + 1;
+                    ui.add(
+                        DragValue::new(&mut ev_nr)
+                            .clamp_range(1..=nevents)
+                            .suffix(format!("/{nevents}")),
+                    );
+                    let target = ev_nr - 1;
+                    let in_window = (offset..offset + self.events.len())
+                        .contains(&target);
+                    if self.stream.is_some() && !in_window {
+                        self.seek_stream(target);
+                    } else {
+                        self.event_idx = target - offset;
+                    }
+                    let can_forward = 1 + self.event_idx < self.events.len();
+                    let forward_button =
+                        ui.add_enabled(can_forward, egui::Button::new("⇨"));
+                    if forward_button.clicked() {
+                        self.event_idx += 1;
+                    }
+                    let animation = &mut self.animation;
+                    let play_label =
+                        if animation.playing { "⏸" } else { "▶" };
+                    let play_button = ui.add_enabled(
+                        !self.events.is_empty(),
+                        egui::Button::new(play_label),
+                    );
+                    if play_button.clicked() {
+                        animation.playing = !animation.playing;
+                        animation.last_step = None;
+                    }
+                    ui.add(
+                        DragValue::new(&mut animation.rate)
+                            .speed(0.1)
+                            .clamp_range(0.1..=60.)
+                            .suffix(" events/s"),
+                    );
+                    ui.checkbox(&mut animation.looping, "Loop");
+                });
+                let width = controls.response.rect.width();
+                if width != self.bottom_panel.controls_width {
+                    self.bottom_panel.controls_width = width;
+                    ui.ctx().request_repaint();
+                }
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("Observable")
+                    .selected_text(self.observable.to_string())
+                    .show_ui(ui, |ui| {
+                        for obs in Observable::iter() {
+                            ui.selectable_value(
+                                &mut self.observable,
+                                obs,
+                                obs.to_string(),
+                            );
+                        }
+                    });
+                if let Some(event) = self.events.get(self.event_idx) {
+                    let val = self.observable.eval(event, &self.jets);
+                    ui.label(format!("= {val:.2}"));
+                }
+                let jump = ui.add_enabled(
+                    !self.events.is_empty(),
+                    egui::Button::new("Jump to maximum"),
                 );
-                self.event_idx = ev_nr - 1;
-                let can_forward = 1 + self.event_idx < self.events.len();
-                let forward_button =
-                    ui.add_enabled(can_forward, egui::Button::new("⇨"));
-                if forward_button.clicked() {
-                    self.event_idx += 1;
-                }
-                self.bottom_panel.space =
-                    (self.bottom_panel.space + ui.available_width()) / 2.;
-            })
+                if jump.clicked() {
+                    self.jump_to_max_observable();
+                }
+                let find = ui.add_enabled(
+                    !self.events.is_empty(),
+                    egui::Button::new("Find next"),
+                );
+                if find.clicked() {
+                    self.find_next_event();
+                }
+                if ui.button("…").on_hover_text("Search condition").clicked() {
+                    self.find_event.is_open = true;
+                }
+                ui.separator();
+                ui.label(self.coordinates_label())
+                    .on_hover_text("Press C or Shift+C to switch coordinates");
+            });
+            if let Some(particle) = self.selected_particle() {
+                ui.horizontal(|ui| {
+                    let name = particle
+                        .id
+                        .symbol()
+                        .map(|s| s.to_owned())
+                        .unwrap_or_else(|| particle.id.id().to_string());
+                    ui.label(format!(
+                        "Selected: {name}, pT = {:.2} GeV, y = {:.2}, φ = {:.2}",
+                        particle.pt, particle.y, particle.phi
+                    ));
+                });
+            }
+            if let [a, b] = self.pair.as_slice() {
+                let m = invariant_mass(a, b);
+                let delta_r = a.delta_r(b);
+                let (a, b) = (a.name(), b.name());
+                ui.horizontal(|ui| {
+                    ui.label(format!("m({a}, {b}) = {m:.2} GeV"));
+                    ui.label(format!("ΔR({a}, {b}) = {delta_r:.3}"));
+                    if ui.button("Clear").clicked() {
+                        self.pair.clear();
+                    }
+                });
+            } else if !self.pair.is_empty() {
+                ui.label("Shift-click a second particle for the invariant mass");
+            }
         });
     }
 
+    fn particles_by_pt(&mut self) -> &[Particle] {
+        self.particles_by_pt.get(
+            &self.events,
+            self.event_idx,
+            &self.plotter.settings,
+        )
+    }
+
+    fn selected_particle(&mut self) -> Option<Particle> {
+        let idx = self.selected_particle?;
+        self.particles_by_pt().get(idx).cloned()
+    }
+
+    fn cycle_selection(&mut self, forward: bool) {
+        let nparticles = self.particles_by_pt().len();
+        if nparticles == 0 {
+            self.selected_particle = None;
+            return;
+        }
+        self.selected_particle = Some(match self.selected_particle {
+            None if forward => 0,
+            None => nparticles - 1,
+            Some(idx) if forward => (idx + 1) % nparticles,
+            Some(idx) => (idx + nparticles - 1) % nparticles,
+        });
+    }
+
+    fn open_style_editor(
+        &mut self,
+        particles: &[Particle],
+        extend: bool,
+        ctx: &Context,
+    ) {
+        let ids = particles.iter().map(|p| p.id);
+        self.particle_style_choice_win.select(ids, extend);
+        self.particle_style_choice_win
+            .set_pos(ctx.pointer_interact_pos());
+    }
+
+    fn jump_to_max_observable(&mut self) {
+        let max = self
+            .events
+            .iter()
+            .map(|event| self.observable.eval(event, &self.jets_for(event)))
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((idx, _)) = max {
+            self.event_idx = idx;
+        }
+    }
+
+    fn animate(&mut self, ctx: &Context) {
+        let animation = &mut self.animation;
+        if !animation.playing {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        let interval = 1. / animation.rate;
+        // manual navigation restarts the timer
+        let last_step = match animation.last_step {
+            Some((idx, time)) if idx == self.event_idx => time,
+            _ => now,
+        };
+        let mut next_step = last_step + interval;
+        if now >= next_step {
+            if self.event_idx + 1 < self.events.len() {
+                self.event_idx += 1;
+            } else if animation.looping && !self.events.is_empty() {
+                self.event_idx = 0;
+            } else {
+                animation.playing = false;
+                return;
+            }
+            animation.last_step = Some((self.event_idx, now));
+            next_step = now + interval;
+        } else {
+            animation.last_step = Some((self.event_idx, last_step));
+        }
+        ctx.request_repaint_after(Duration::from_secs_f64(next_step - now));
+    }
+
+    fn find_next_event(&mut self) {
+        let predicate = self.find_event.predicate;
+        match predicate.find_next(&self.events, self.event_idx) {
+            Some(idx) => self.event_idx = idx,
+            None => self.msg = "No matching event found".to_owned(),
+        }
+    }
+
     fn recluster(&mut self) {
+        if self.clustering.clustering_enabled && self.clustering.show_sequence {
+            self.plotter.r_jet = self.clustering.jet_def.radius;
+            self.cluster_key = None;
+            self.plotter.last_merge = None;
+            self.plotter.constituents.clear();
+            self.clustering.summary = None;
+            if self.events.get(self.event_idx).is_some() {
+                self.step_through_sequence();
+            } else {
+                self.jets.clear()
+            }
+            return;
+        }
+        let key = (
+            self.event_idx,
+            self.events.len(),
+            self.clustering.jet_def,
+            self.clustering.clustering_enabled,
+        );
+        if self.cluster_key == Some(key) {
+            return;
+        }
+        self.cluster_key = Some(key);
+        self.plotter.r_jet = self.clustering.jet_def.radius;
+        self.plotter.constituents.clear();
+        self.clustering.summary = None;
         if !self.clustering.clustering_enabled {
             self.jets.clear();
             return;
         }
+        self.plotter.last_merge = None;
         if let Some(event) = self.events.get(self.event_idx) {
-            self.jets = cluster(event, &self.clustering.jet_def);
+            let jet_def = &self.clustering.jet_def;
+            let clustered = cluster_with_constituents(event, jet_def);
+            self.plotter.r_jet = jet_area_radius(event, jet_def, &clustered);
+            self.clustering.summary =
+                Some(ClusterSummary::new(event, &clustered));
+            self.jets = Vec::from_iter(clustered.iter().map(|(jet, _)| *jet));
+            self.plotter.constituents =
+                Vec::from_iter(clustered.into_iter().map(|(jet, idx)| {
+                    (jet, idx.into_iter().map(|i| event.out[i]).collect())
+                }));
         } else {
             self.jets.clear()
         }
-        self.plotter.r_jet = self.clustering.jet_def.radius;
         trace!("recluster: {:#?}", self.jets);
     }
 
+    fn step_through_sequence(&mut self) {
+        let Some(event) = self.events.get(self.event_idx) else {
+            return;
+        };
+        let key = (self.event_idx, self.events.len(), self.clustering.jet_def);
+        if self.sequence_key != Some(key) {
+            self.sequence = cluster_sequence(event, &self.clustering.jet_def);
+            self.sequence_key = Some(key);
+        }
+        let state = self.sequence.state_after(self.clustering.sequence_step);
+        self.jets = state.jets;
+        self.jets.extend(state.proto_jets);
+        self.plotter.last_merge = state.last_merge;
+    }
+
     fn draw_central_panel(&mut self, ctx: &Context, event: &Event) {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.weak(&self.msg);
             let Vec2 { x, y } = ui.available_size();
             let [width, height] = [x as usize, y as usize];
             let mut img = vec![0u8; width * height * BYTES_PER_RGBA_PIXEL];
-            self.plotter
-                .plot_3d(event, &self.jets, &mut img, [width, height])
-                .unwrap();
+            // `Instant` is not available on the web
+            #[cfg(not(target_arch = "wasm32"))]
+            let start = std::time::Instant::now();
+            let res = self.plotter.plot_3d(
+                event,
+                &self.jets,
+                &mut img,
+                [width, height],
+            );
+            if let Err(err) = res {
+                // skip this frame, the panel may just be too small
+                let msg = format!("Failed to draw 3D plot: {err:#}");
+                if self.plot_3d_error.as_ref() != Some(&msg) {
+                    self.msg.clone_from(&msg);
+                    self.plot_3d_error = Some(msg);
+                }
+                return;
+            }
+            self.plot_3d_error = None;
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.diagnostics.render_3d_time = Some(start.elapsed());
+            }
             rgb_to_rgba(&mut img);
             let img = egui::ColorImage::from_rgba_premultiplied(
                 [width, height],
@@ -279,8 +990,23 @@ impl TemplateApp {
             let img = Image::from_texture(img)
                 .sense(Sense::click_and_drag());
             let response = ui.add(img);
+            let projection = &mut self.plotter.settings_3d.projection;
+            if response.hovered() {
+                let scroll = ui.input(|i| i.scroll_delta.y) as f64;
+                if scroll != 0. {
+                    projection.scale = (projection.scale
+                        * (SCROLL_ZOOM_SPEED * scroll).exp())
+                    .clamp(MIN_3D_SCALE, MAX_3D_SCALE);
+                }
+            }
             if response.double_clicked() {
                 self.plotter.settings_3d.rotation = Rotation3::identity();
+            } else if response.dragged_by(egui::PointerButton::Secondary) {
+                // move the camera around the scene
+                let drag = response.drag_delta();
+                projection.yaw += 0.01 * drag.x as f64;
+                projection.pitch = (projection.pitch + 0.01 * drag.y as f64)
+                    .clamp(-FRAC_PI_2, FRAC_PI_2);
             } else if response.dragged() {
                 let drag = response.drag_delta();
                 let dist = 0.01 * drag.length() as f64;
@@ -294,7 +1020,445 @@ impl TemplateApp {
         });
     }
 
+    fn draw_compare_panel(&mut self, ctx: &Context) {
+        let mut response = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.weak(&self.msg);
+            ui.checkbox(&mut self.compare.sync, "Synchronise navigation");
+            ui.columns(2, |columns| {
+                for (side, ui) in columns.iter_mut().enumerate() {
+                    ui.push_id(side, |ui| {
+                        let idx = if side == 0 {
+                            &mut self.event_idx
+                        } else {
+                            &mut self.compare.other_idx
+                        };
+                        ui.horizontal(|ui| {
+                            let nevents = self.events.len();
+                            if ui
+                                .add_enabled(*idx > 0, egui::Button::new("⇦"))
+                                .clicked()
+                            {
+                                *idx -= 1;
+                            }
+                            let mut ev_nr = *idx + 1;
+                            ui.add(
+                                DragValue::new(&mut ev_nr)
+                                    .clamp_range(1..=nevents)
+                                    .suffix(format!("/{nevents}")),
+                            );
+                            *idx = ev_nr - 1;
+                            let can_forward = *idx + 1 < nevents;
+                            if ui
+                                .add_enabled(
+                                    can_forward,
+                                    egui::Button::new("⇨"),
+                                )
+                                .clicked()
+                            {
+                                *idx += 1;
+                            }
+                        });
+                        let idx = *idx;
+                        let Some(event) = self.events.get(idx) else {
+                            return;
+                        };
+                        let jets = if idx == self.event_idx {
+                            &self.jets
+                        } else {
+                            self.compare
+                                .other_jets(&self.events, &self.clustering)
+                        };
+                        let res = self.plotter.plot_y_phi(
+                            ui,
+                            event,
+                            jets,
+                            Default::default(),
+                            false,
+                            None,
+                            None,
+                        );
+                        if let Some(res) = res {
+                            response = Some((res, idx));
+                        }
+                    });
+                }
+            });
+        });
+        if let Some((response, idx)) = response {
+            self.handle_plot_response(ctx, Some(response), idx);
+        }
+    }
+
+    fn handle_plot_response(
+        &mut self,
+        ctx: &Context,
+        response: Option<PlotResponse>,
+        event_idx: usize,
+    ) {
+        match response {
+            Some(PlotResponse::Selected { particles, extend }) => {
+                if event_idx == self.event_idx {
+                    if let Some(particle) = particles.last() {
+                        self.selected_particle = self
+                            .particles_by_pt()
+                            .iter()
+                            .position(|p| p == particle);
+                    }
+                }
+                self.open_style_editor(&particles, extend, ctx);
+            }
+            Some(PlotResponse::AddedToPair(particle)) => {
+                if event_idx != self.event_idx {
+                    return;
+                }
+                if let Some(pos) = self.pair.iter().position(|p| *p == particle)
+                {
+                    self.pair.remove(pos);
+                } else {
+                    if self.pair.len() == 2 {
+                        self.pair.remove(0);
+                    }
+                    self.pair.push(particle);
+                }
+            }
+            Some(PlotResponse::SelectedJet(jet)) => {
+                self.jet_selection_win
+                    .select(jet, ctx.pointer_interact_pos());
+            }
+            Some(PlotResponse::Export { kind, format }) => {
+                self.export_win.kind = kind;
+                self.export_win.format = format;
+                self.export_win.event_id = event_idx;
+                self.export_win.open();
+            }
+            None => {}
+        }
+    }
+
+    fn jets_for(&self, event: &Event) -> Vec<PseudoJet> {
+        if self.clustering.clustering_enabled {
+            cluster(event, &self.clustering.jet_def)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn load_files(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.events.clear();
+        self.event_idx = 0;
+        self.stream = None;
+        // the new events may have the same number as the old ones
+        self.clear_caches();
+        let paths = Vec::from_iter(paths);
+        // streaming only makes sense for a single file
+        let stream = self.stream_files
+            && paths.len() == 1
+            && stream::is_streamable(&paths[0]);
+        for path in paths {
+            if let Some(path) = path.to_str() {
+                let job = if stream {
+                    Job::Index(path.to_owned())
+                } else {
+                    Job::Load(path.to_owned())
+                };
+                let _ = self.s_job.as_mut().unwrap().send(job);
+            } else {
+                self.msg =
+                    format!("Failed to open {path:?}: Cannot convert to UTF-8");
+            }
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &Context) {
+        let hovered = ctx.input(|i| !i.raw.hovered_files.is_empty());
+        if hovered {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("file drop target"),
+            ));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, Color32::from_black_alpha(192));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop event files to open them",
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                Color32::WHITE,
+            );
+        }
+
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        if dropped.is_empty() {
+            return;
+        }
+        let mut paths = Vec::with_capacity(dropped.len());
+        for file in dropped {
+            if let Some(path) = file.path {
+                paths.push(path);
+            } else {
+                // happens on the web, where we only get the file content
+                self.msg = format!("Cannot open dropped file {}", file.name);
+            }
+        }
+        if !paths.is_empty() {
+            self.load_files(paths);
+        }
+    }
+
+    fn show_reset_confirmation(&mut self, ctx: &Context) {
+        if !self.confirm_reset {
+            return;
+        }
+        let mut reset = false;
+        egui::Window::new("Reset all settings")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Reset all settings to their defaults? \
+                     Loaded events are kept.",
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Reset").clicked() {
+                        reset = true;
+                        self.confirm_reset = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_reset = false;
+                    }
+                });
+            });
+        if reset {
+            self.reset_settings(ctx);
+        }
+    }
+
+    fn reset_settings(&mut self, ctx: &Context) {
+        debug!("Resetting all settings");
+        let old = std::mem::take(self);
+        *self = Self {
+            events: old.events,
+            event_idx: old.event_idx,
+            msg: old.msg,
+            s_job: old.s_job,
+            r_ev: old.r_ev,
+            r_msg: old.r_msg,
+            r_index: old.r_index,
+            stream: old.stream,
+            plot_3d: old.plot_3d,
+            // a running batch export must remain cancellable
+            batch_cancel: old.batch_cancel,
+            ..Default::default()
+        };
+        // forget window positions and sizes
+        ctx.memory_mut(|mem| mem.reset_areas());
+    }
+
+    /// Switch to the next or previous combination of longitudinal
+    /// coordinate, transverse momentum scale, and φ range
+    fn cycle_coordinates(&mut self, forward: bool) {
+        let rapidities = Vec::from_iter(Rapidity::iter());
+        let pt_scales = Vec::from_iter(PtScale::iter());
+        let r_idx = rapidities
+            .iter()
+            .position(|r| *r == self.plotter.settings.rapidity)
+            .unwrap_or_default();
+        let pt_idx = pt_scales
+            .iter()
+            .position(|s| *s == self.y_log_pt.pt_scale)
+            .unwrap_or_default();
+        let phi_idx = usize::from(self.y_phi.fixed_phi_range);
+        // count through all combinations, changing the rapidity fastest
+        let sizes = [rapidities.len(), pt_scales.len(), 2];
+        let nmodes: usize = sizes.iter().product();
+        let mode = r_idx + sizes[0] * (pt_idx + sizes[1] * phi_idx);
+        let mode = if forward {
+            (mode + 1) % nmodes
+        } else {
+            (mode + nmodes - 1) % nmodes
+        };
+        self.plotter.settings.rapidity = rapidities[mode % sizes[0]];
+        self.y_log_pt.pt_scale = pt_scales[(mode / sizes[0]) % sizes[1]];
+        self.y_phi.fixed_phi_range = mode / (sizes[0] * sizes[1]) == 1;
+        self.plotter.reset_view = true;
+    }
+
+    /// Short description of the current plot coordinates
+    fn coordinates_label(&self) -> String {
+        let phi_range = if self.y_phi.fixed_phi_range {
+            "fixed φ range"
+        } else {
+            "periodic φ"
+        };
+        format!(
+            "{}, {} pT, {phi_range}",
+            self.plotter.settings.rapidity, self.y_log_pt.pt_scale
+        )
+    }
+
+    fn start_stream(&mut self, index: EventIndex) {
+        let nevents = index.len();
+        self.stream = Some(EventStream::new(index));
+        self.seek_stream(0);
+        if let Some(stream) = &self.stream {
+            self.msg =
+                format!("Streaming {nevents} events from {:?}", stream.path());
+        }
+    }
+
+    /// Index of the first event in `events` within the whole sample
+    fn stream_offset(&self) -> usize {
+        self.stream.as_ref().map_or(0, |stream| stream.start())
+    }
+
+    /// Move the window of streamed events once we get close to its edges
+    fn follow_stream(&mut self) {
+        let Some(stream) = &self.stream else {
+            return;
+        };
+        if stream.needs_seek(self.event_idx, self.events.len()) {
+            let idx = stream.start() + self.event_idx;
+            self.seek_stream(idx);
+        }
+    }
+
+    /// Read the streamed events around the event with global index `idx`
+    fn seek_stream(&mut self, idx: usize) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        debug!("Seeking to event {idx} in {:?}", stream.path());
+        match stream.seek(idx) {
+            Ok((events, idx)) => {
+                self.events = events;
+                self.event_idx = idx;
+            }
+            Err(err) => {
+                error!("Failed to read events: {err:#}");
+                self.msg = format!("Failed to read events: {err:#}");
+                self.stream = None;
+                self.events.clear();
+                self.event_idx = 0;
+            }
+        }
+        // the window has changed, so everything derived from it is stale
+        self.clear_caches();
+    }
+
+    /// Forget everything computed from the currently loaded events
+    fn clear_caches(&mut self) {
+        self.cluster_key = None;
+        self.sequence_key = None;
+        self.compare.jets_key = None;
+        self.particles_by_pt.key = None;
+        self.thumbnails.clear_cache();
+        self.detector.clear_cache();
+        self.pt_spectrum.clear_cache();
+        self.rapidity_distribution.clear_cache();
+    }
+
+    fn handle_preset(&mut self, action: PresetAction, path: &Path) {
+        let res = match action {
+            PresetAction::Save => Preset::from_plotter(&self.plotter)
+                .save(path)
+                .map(|()| format!("Saved style preset to {path:?}")),
+            PresetAction::Load => Preset::load(path).map(|preset| {
+                preset.apply(&mut self.plotter);
+                format!("Loaded style preset from {path:?}")
+            }),
+        };
+        self.msg = match res {
+            Ok(msg) => msg,
+            Err(err) => {
+                error!("{err:#}");
+                format!("{err:#}")
+            }
+        };
+    }
+
+    fn show_batch_export(&mut self, ctx: &Context) {
+        // the background thread drops its handle once it is done
+        if self
+            .batch_cancel
+            .as_ref()
+            .is_some_and(|cancel| Arc::strong_count(cancel) == 1)
+        {
+            self.batch_cancel = None;
+        }
+        let cancel = self.batch_cancel.as_deref();
+        let Some(dir) = self.batch_export_win.show(ctx, cancel) else {
+            return;
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let batch = BatchExport {
+            dir,
+            events: self.events.clone(),
+            jet_def: self
+                .clustering
+                .clustering_enabled
+                .then_some(self.clustering.jet_def),
+            kind: self.batch_export_win.kind,
+            format: self.batch_export_win.format,
+            settings: self.plotter.settings.clone(),
+            options: self.export_win.options,
+            cancel: cancel.clone(),
+        };
+        let job = Job::Export(Box::new(batch));
+        if self.s_job.as_mut().unwrap().send(job).is_ok() {
+            self.batch_cancel = Some(cancel);
+        }
+    }
+
+    fn reset_view(&mut self, ctx: &Context) {
+        debug!("Resetting view");
+        let default = Settings3D::default();
+        self.plotter.settings_3d.projection = default.projection;
+        self.plotter.settings_3d.rotation = default.rotation;
+        self.plotter.settings.min_pt.clear();
+        self.plotter.reset_view = true;
+        ctx.request_repaint();
+    }
+
+    fn reset_styles(&mut self, ctx: &Context) {
+        debug!("Resetting styles");
+        let default = Settings::default();
+        self.plotter.settings.particles = default.particles;
+        self.plotter.settings.jets = default.jets;
+        self.plotter.highlight = None;
+        ctx.request_repaint();
+    }
+
+    fn show_diagnostics(&self, ctx: &Context, frame: &eframe::Frame) {
+        if !self.diagnostics.enabled {
+            return;
+        }
+        egui::Area::new("diagnostics")
+            .order(egui::Order::Foreground)
+            .anchor(egui::Align2::RIGHT_TOP, [-10., 40.])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    let dt = ctx.input(|i| i.unstable_dt);
+                    ui.label(format!("Frame time: {:.1} ms", 1000. * dt));
+                    if let Some(cpu) = frame.info().cpu_usage {
+                        ui.label(format!("CPU time: {:.1} ms", 1000. * cpu));
+                    }
+                    if let Some(t) = self.diagnostics.render_3d_time {
+                        let t = t.as_secs_f64() * 1000.;
+                        ui.label(format!("3D render time: {t:.1} ms"));
+                    }
+                    let nmarkers = self.plotter.drawn_markers;
+                    ui.label(format!("Drawn markers: {nmarkers}"));
+                    ui.label(format!("Jets: {}", self.jets.len()));
+                });
+            });
+    }
+
     fn check_input(&mut self, ctx: &Context) {
+        let has_focus = ctx.memory(|m| m.focus().is_some());
+        let (mut cycle, mut edit) = (None, false);
+        let mut cycle_coordinates = None;
         ctx.input_mut(|i| {
             let ctrl_q = KeyboardShortcut::new(Modifiers::CTRL, egui::Key::Q);
             if i.consume_shortcut(&ctrl_q) {
@@ -305,6 +1469,38 @@ impl TemplateApp {
             if i.consume_shortcut(&ctrl_o) {
                 self.open_file_win.open();
             }
+            let f12 = KeyboardShortcut::new(Modifiers::NONE, egui::Key::F12);
+            if i.consume_shortcut(&f12) {
+                self.diagnostics.enabled = !self.diagnostics.enabled;
+            }
+            if !has_focus {
+                let tab =
+                    KeyboardShortcut::new(Modifiers::NONE, egui::Key::Tab);
+                let shift_tab =
+                    KeyboardShortcut::new(Modifiers::SHIFT, egui::Key::Tab);
+                let enter =
+                    KeyboardShortcut::new(Modifiers::NONE, egui::Key::Enter);
+                let escape =
+                    KeyboardShortcut::new(Modifiers::NONE, egui::Key::Escape);
+                if i.consume_shortcut(&shift_tab) {
+                    cycle = Some(false);
+                } else if i.consume_shortcut(&tab) {
+                    cycle = Some(true);
+                }
+                edit = i.consume_shortcut(&enter);
+                let c = KeyboardShortcut::new(Modifiers::NONE, egui::Key::C);
+                let shift_c =
+                    KeyboardShortcut::new(Modifiers::SHIFT, egui::Key::C);
+                if i.consume_shortcut(&shift_c) {
+                    cycle_coordinates = Some(false);
+                } else if i.consume_shortcut(&c) {
+                    cycle_coordinates = Some(true);
+                }
+                if i.consume_shortcut(&escape) {
+                    self.selected_particle = None;
+                    self.pair.clear();
+                }
+            }
             let right =
                 KeyboardShortcut::new(Modifiers::NONE, egui::Key::ArrowRight);
             if i.consume_shortcut(&right) && !self.events.is_empty() {
@@ -319,11 +1515,232 @@ impl TemplateApp {
                     self.event_idx -= 1;
                 }
             };
-        })
+        });
+        if let Some(forward) = cycle {
+            self.cycle_selection(forward);
+        }
+        if let Some(forward) = cycle_coordinates {
+            self.cycle_coordinates(forward);
+        }
+        if edit {
+            if let Some(particle) = self.selected_particle() {
+                self.open_style_editor(&[particle], false, ctx);
+            }
+        }
+    }
+}
+
+/// Load events from a file or archive, reporting progress through `s_msg`
+///
+/// Returns `false` if the receiving end has hung up.
+pub(crate) fn load_file(
+    file: &str,
+    s_ev: &Sender<Event>,
+    s_msg: &Sender<String>,
+) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    if file == STDIN_ARG {
+        return load_stdin(s_ev, s_msg);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if archive::is_archive(Path::new(file)) {
+        return load_archive(file, s_ev, s_msg);
+    }
+    if s_msg.send(format!("Loading events from {file}")).is_err() {
+        return false;
+    }
+    if let Err(err) = send_file_events(Path::new(file), file, s_ev, s_msg) {
+        return s_msg
+            .send(format!("Failed to read from {file}: {err:#}"))
+            .is_ok();
+    }
+    s_msg.send(String::new()).is_ok()
+}
+
+/// Build the index for streaming events from a file
+///
+/// Returns `false` if the receiving end has hung up.
+fn index_file(
+    file: &str,
+    s_index: &Sender<EventIndex>,
+    s_msg: &Sender<String>,
+) -> bool {
+    if s_msg.send(format!("Indexing events in {file}")).is_err() {
+        return false;
+    }
+    match EventIndex::build(Path::new(file)) {
+        Ok(index) => s_index.send(index).is_ok(),
+        Err(err) => s_msg
+            .send(format!("Failed to index {file}: {err:#}"))
+            .is_ok(),
+    }
+}
+
+/// Load events piped to standard input
+///
+/// Returns `false` if the receiving end has hung up.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_stdin(s_ev: &Sender<Event>, s_msg: &Sender<String>) -> bool {
+    let msg = format!("Loading events from {STDIN_NAME}");
+    if s_msg.send(msg).is_err() {
+        return false;
+    }
+    if let Err(err) = send_stdin_events(s_ev, s_msg) {
+        return s_msg
+            .send(format!("Failed to read from {STDIN_NAME}: {err:#}"))
+            .is_ok();
+    }
+    s_msg.send(String::new()).is_ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn send_stdin_events(
+    s_ev: &Sender<Event>,
+    s_msg: &Sender<String>,
+) -> anyhow::Result<()> {
+    use std::io::{BufRead, Write};
+
+    use crate::auto_decompress::auto_decompress;
+
+    let input = prefill(std::io::stdin(), STDIN_SNIFF_LEN)?;
+    let mut input = prefill(auto_decompress(input)?, STDIN_SNIFF_LEN)?;
+    if hepmc3::has_header(&mut input) {
+        let events = hepmc3::Reader::from_input(input)
+            .into_events()
+            .map(|event| event.map(|event| (event, 0)));
+        send_events(events, STDIN_NAME, s_ev, s_msg);
+        return Ok(());
+    }
+    // event_file_reader can only read from files, so spool the input
+    let mut file = tempfile::Builder::new().prefix("evil-stdin-").tempfile()?;
+    let mut nbytes = 0;
+    loop {
+        let buf = input.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        file.write_all(buf)?;
+        let len = buf.len();
+        input.consume(len);
+        let before = nbytes / STDIN_PROGRESS_BYTES;
+        nbytes += len;
+        if nbytes / STDIN_PROGRESS_BYTES > before {
+            let mib = nbytes >> 20;
+            let msg = format!("Reading from {STDIN_NAME}: {mib} MiB");
+            if s_msg.send(msg).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    file.flush()?;
+    send_file_events(file.path(), STDIN_NAME, s_ev, s_msg)
+}
+
+/// Buffer the first `len` bytes of `input`, or all of it if it is shorter
+///
+/// A single read from a pipe can return less data than is needed to
+/// detect the format from what `fill_buf` returns.
+#[cfg(not(target_arch = "wasm32"))]
+fn prefill(
+    mut input: impl std::io::Read + Send + 'static,
+    len: usize,
+) -> std::io::Result<Box<dyn std::io::BufRead + Send>> {
+    use std::io::{BufReader, Cursor, Read};
+
+    let mut start = Vec::with_capacity(len);
+    input.by_ref().take(len as u64).read_to_end(&mut start)?;
+    Ok(Box::new(BufReader::new(Cursor::new(start).chain(input))))
+}
+
+/// Load all event files in an archive
+///
+/// Returns `false` if the receiving end has hung up.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_archive(
+    file: &str,
+    s_ev: &Sender<Event>,
+    s_msg: &Sender<String>,
+) -> bool {
+    if s_msg.send(format!("Unpacking {file}")).is_err() {
+        return false;
+    }
+    let unpacked = match archive::unpack(Path::new(file)) {
+        Ok(unpacked) => unpacked,
+        Err(err) => return s_msg.send(format!("{err:#}")).is_ok(),
+    };
+    let nfiles = unpacked.files.len();
+    for (n, path) in unpacked.files.iter().enumerate() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let msg =
+            format!("Loading events from {file} ({}/{nfiles}): {name}", n + 1);
+        if s_msg.send(msg).is_err() {
+            return false;
+        }
+        if let Err(err) = send_file_events(path, &name, s_ev, s_msg) {
+            warn!("Skipping {name} in {file}: {err:#}");
+        }
+    }
+    s_msg.send(String::new()).is_ok()
+}
+
+/// Send all events in a file, choosing the reader based on the file header
+fn send_file_events(
+    path: &Path,
+    name: &str,
+    s_ev: &Sender<Event>,
+    s_msg: &Sender<String>,
+) -> anyhow::Result<()> {
+    if hepmc3::is_hepmc3(path) {
+        let reader = hepmc3::Reader::new(path)?;
+        let events = reader
+            .into_events()
+            .map(|event| event.map(|event| (event, 0)));
+        send_events(events, name, s_ev, s_msg);
+    } else {
+        let reader =
+            Reader::new(path).map_err(|err| anyhow::anyhow!("{err}"))?;
+        let events = reader.map(|event| event.map(Event::from_avery));
+        send_events(events, name, s_ev, s_msg);
+    }
+    Ok(())
+}
+
+/// Send events together with the number of particles skipped in each
+fn send_events<E: std::fmt::Display>(
+    events: impl IntoIterator<Item = Result<(Event, usize), E>>,
+    file: &str,
+    s_ev: &Sender<Event>,
+    s_msg: &Sender<String>,
+) {
+    let mut nskipped = 0;
+    let mut npartial = 0;
+    for event in events {
+        match event {
+            Ok((event, skipped)) => {
+                if skipped > 0 {
+                    nskipped += skipped;
+                    npartial += 1;
+                }
+                if s_ev.send(event).is_err() {
+                    break;
+                }
+            }
+            Err(err) => {
+                let _ =
+                    s_msg.send(format!("Failed to read from {file}: {err}"));
+            }
+        }
+    }
+    if nskipped > 0 {
+        warn!("Skipped {nskipped} particles without id or momentum in {file}");
+        let _ = s_msg.send(format!(
+            "Skipped {nskipped} particles without id or momentum \
+             in {npartial} events from {file}"
+        ));
     }
 }
 
-fn rgb_to_rgba(img: &mut [u8]) {
+pub(crate) fn rgb_to_rgba(img: &mut [u8]) {
     // insert 0 alpha values
     // start at the end of `img` so we can safely do internal copies
     debug_assert_eq!(img.len() % BYTES_PER_RGBA_PIXEL, 0);
@@ -353,7 +1770,20 @@ impl eframe::App for TemplateApp {
         while let Ok(ev) = self.r_ev.as_mut().unwrap().try_recv() {
             self.events.push(ev);
         }
+        while let Ok(index) = self.r_index.as_mut().unwrap().try_recv() {
+            self.start_stream(index);
+        }
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = Some(rect.size());
+        }
+        let old_idx = self.event_idx;
+        self.animate(ctx);
+        self.follow_stream();
         self.recluster();
+        self.plotter.drawn_markers = 0;
+        self.plotter.selected = self.pair.clone();
+        let selected = self.selected_particle();
+        self.plotter.selected.extend(selected);
 
         egui::TopBottomPanel::top("top_panel")
             .show(ctx, |ui| self.menu(ctx, ui, frame));
@@ -367,60 +1797,132 @@ impl eframe::App for TemplateApp {
         let response_phi =
             self.y_phi.show(ctx, &mut self.plotter, &event, &self.jets);
         let response = response_logpt.or(response_phi);
-        match response {
-            Some(PlotResponse::Selected(particle)) => {
-                self.particle_style_choice_win.id = particle.id;
-                self.particle_style_choice_win
-                    .set_pos(ctx.pointer_interact_pos());
-                self.particle_style_choice_win.is_open = true;
-            }
-            Some(PlotResponse::Export { kind, format }) => {
-                self.export_win.kind = kind;
-                self.export_win.format = format;
-                self.export_win.event_id = self.event_idx;
-                self.export_win.open();
-            }
-            None => {}
+        self.handle_plot_response(ctx, response, self.event_idx);
+
+        self.event_info.show(ctx, &event);
+        self.event_summary.show(ctx, &event);
+        if self.find_event.show(ctx) {
+            self.find_next_event();
+        }
+
+        self.conventions.show(ctx, &self.clustering, &self.plotter);
+
+        self.jet_list.show(
+            ctx,
+            &self.jets,
+            &self.plotter.constituents,
+            &self.clustering.jet_def,
+        );
+
+        self.pt_spectrum
+            .show(ctx, &self.events, &self.plotter.settings);
+        self.rapidity_distribution.show(
+            ctx,
+            &self.events,
+            &self.plotter.settings,
+        );
+
+        self.detector_geometry
+            .show(ctx, &mut self.plotter.settings_3d.detector);
+        self.detector.show(
+            ctx,
+            &mut self.plotter,
+            self.event_idx,
+            &event,
+            &self.jets,
+        );
+
+        self.display_cuts
+            .show(ctx, &mut self.plotter.settings.display_cuts);
+
+        if let Some(idx) =
+            self.thumbnails.show(ctx, &mut self.plotter, &self.events)
+        {
+            self.event_idx = idx;
         }
 
         self.particle_style_choice_win
             .show(ctx, &mut self.plotter.settings);
 
-        if self.clustering.changed(ctx) {
+        self.jet_selection_win.show(ctx, &mut self.plotter);
+
+        if self.clustering.changed(ctx, self.sequence.len()) {
             debug!("Clustering changed to {:?}", self.clustering);
         }
 
         let kind = self.export_win.kind;
         let format = self.export_win.format;
+        let export_idx = self.export_win.event_id;
         if let Some(path) = self.export_win.show(ctx) {
+            let event = self.events.get(export_idx).unwrap_or(&dummy);
+            let export_jets = if export_idx == self.event_idx {
+                self.jets.clone()
+            } else {
+                self.jets_for(event)
+            };
+            let provenance = Provenance {
+                event_idx: self.stream_offset() + export_idx,
+                jet_def: self
+                    .clustering
+                    .clustering_enabled
+                    .then_some(self.clustering.jet_def),
+            };
             if let Err(err) = export(
-                path,
-                &event,
-                &self.jets,
+                &path,
+                event,
+                &export_jets,
                 self.plotter.r_jet,
                 kind,
                 format,
                 &self.plotter.settings,
+                &self.export_win.options,
+                &provenance,
             ) {
                 error!("{err}");
                 self.msg = err.to_string();
             }
         }
 
+        self.show_batch_export(ctx);
+
         if let Some(path) = self.open_file_win.show(ctx) {
-            if let Some(path) = path.to_str() {
-                self.events.clear();
-                let _ = self.s_file.as_mut().unwrap().send(path.to_owned());
-            } else {
-                self.msg =
-                    format!("Failed to open {path:?}: Cannot convert to UTF-8");
-            }
+            let path = path.to_owned();
+            self.load_files([path]);
         }
 
+        if let Some((action, path)) = self.preset_win.show(ctx) {
+            self.handle_preset(action, &path);
+        }
+
+        self.handle_dropped_files(ctx);
+
+        self.show_reset_confirmation(ctx);
+
         self.draw_bottom_panel(ctx);
 
-        self.draw_central_panel(ctx, &event);
+        if self.compare.enabled {
+            self.draw_compare_panel(ctx);
+        } else {
+            self.draw_central_panel(ctx, &event);
+        }
+
+        self.show_diagnostics(ctx, frame);
 
         self.check_input(ctx);
+
+        self.plotter.reset_view = false;
+
+        if self.event_idx != old_idx {
+            self.plotter.highlight = None;
+            self.selected_particle = None;
+            self.pair.clear();
+        }
+
+        if self.compare.sync && self.event_idx != old_idx {
+            let shift = self.event_idx as isize - old_idx as isize;
+            let other = self.compare.other_idx.saturating_add_signed(shift);
+            let last = self.events.len().saturating_sub(1);
+            self.compare.other_idx = std::cmp::min(other, last);
+        }
     }
 }
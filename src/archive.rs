@@ -0,0 +1,138 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use log::warn;
+use tempfile::TempDir;
+
+const ARCHIVE_SUFFIXES: [&str; 4] = [".tar", ".tar.gz", ".tgz", ".zip"];
+
+/// Check whether a file name looks like a supported archive
+pub(crate) fn is_archive(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let name = name.to_ascii_lowercase();
+    ARCHIVE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Files extracted from an archive into a temporary directory
+///
+/// The directory is removed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct Unpacked {
+    dir: TempDir,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+/// Extract all regular files from a tar or zip archive
+pub(crate) fn unpack(path: &Path) -> Result<Unpacked> {
+    let dir = tempfile::Builder::new()
+        .prefix("evil-")
+        .tempdir()
+        .context("Failed to create temporary directory")?;
+    let mut unpacked = Unpacked {
+        dir,
+        files: Vec::new(),
+    };
+    let file =
+        File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if name.ends_with(".zip") {
+        unpack_zip(file, &mut unpacked)
+    } else if name.ends_with(".tar") {
+        unpack_tar(file, &mut unpacked)
+    } else {
+        unpack_tar(GzDecoder::new(file), &mut unpacked)
+    }
+    .with_context(|| format!("Failed to unpack {path:?}"))?;
+    unpacked.files.sort();
+    Ok(unpacked)
+}
+
+fn unpack_tar(archive: impl io::Read, unpacked: &mut Unpacked) -> Result<()> {
+    let mut archive = tar::Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.into_owned();
+        if entry.unpack_in(unpacked.dir.path())? {
+            // `unpack_in` drops root and current directory components
+            let dest = name
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(part) => Some(part),
+                    _ => None,
+                })
+                .fold(unpacked.dir.path().to_owned(), |dir, part| {
+                    dir.join(part)
+                });
+            unpacked.files.push(dest);
+        } else {
+            warn!("Skipping archive entry {name:?} outside of archive");
+        }
+    }
+    Ok(())
+}
+
+fn unpack_zip(archive: File, unpacked: &mut Unpacked) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(archive)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name().map(|n| n.to_owned()) else {
+            warn!(
+                "Skipping archive entry {:?} outside of archive",
+                entry.name()
+            );
+            continue;
+        };
+        let dest = unpacked.dir.path().join(name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&dest)?)?;
+        unpacked.files.push(dest);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_absolute_tar_entry() {
+        let contents = b"absolute";
+        let mut header = tar::Header::new_gnu();
+        // `Header::set_path` refuses absolute paths
+        let name = b"/etc/evil-test";
+        header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let mut tar = tar::Builder::new(Vec::new());
+        tar.append(&header, &contents[..]).unwrap();
+        let tar = tar.into_inner().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("absolute.tar");
+        fs::write(&path, tar).unwrap();
+        let unpacked = unpack(&path).unwrap();
+        let expected = unpacked.dir.path().join("etc").join("evil-test");
+        assert_eq!(fs::read(&expected).unwrap(), contents);
+        assert_eq!(unpacked.files, [expected]);
+    }
+}
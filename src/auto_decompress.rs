@@ -0,0 +1,36 @@
+use std::io::{BufRead, BufReader};
+
+use anyhow::Result;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+/// Transparently decompress gzip, zstd, or bzip2 compressed input
+///
+/// The compression format is detected from the first bytes of the input,
+/// so this also works for pipes. Uncompressed input is passed through.
+pub(crate) fn auto_decompress(
+    mut input: impl BufRead + Send + 'static,
+) -> Result<Box<dyn BufRead + Send>> {
+    let start = input.fill_buf()?;
+    if start.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(input))))
+    } else if start.starts_with(ZSTD_MAGIC) {
+        let decoder = zstd::stream::read::Decoder::with_buffer(input)?;
+        Ok(Box::new(BufReader::new(decoder)))
+    } else if start.starts_with(BZIP2_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiBzDecoder::new(input))))
+    } else {
+        Ok(Box::new(input))
+    }
+}
+
+/// Check whether data starts with a supported compression format
+pub(crate) fn is_compressed(start: &[u8]) -> bool {
+    [GZIP_MAGIC, ZSTD_MAGIC, BZIP2_MAGIC]
+        .iter()
+        .any(|magic| start.starts_with(magic))
+}
@@ -1,9 +1,18 @@
+use std::collections::HashMap;
+use std::mem::discriminant;
+
+use anyhow::{anyhow, Result};
 use egui::{Context, DragValue};
-use jetty::{anti_kt_f, cambridge_aachen_f, kt_f, Cluster, PseudoJet};
+use jetty::{
+    anti_kt_f, cambridge_aachen_f, gen_kt_f, kt_f, Cluster, ClusterHistory,
+    PseudoJet,
+};
+use log::warn;
 use particle_id::hadrons::HADRONS;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter, IntoEnumIterator};
 
+use crate::particle::{delta_phi, Particle};
 use crate::Event;
 
 #[derive(
@@ -12,11 +21,8 @@ use crate::Event;
     Copy,
     Clone,
     Default,
-    Eq,
     PartialEq,
-    Ord,
     PartialOrd,
-    Hash,
     Debug,
     Deserialize,
     Serialize,
@@ -29,31 +35,690 @@ pub enum JetAlgorithm {
     Kt,
     #[strum(to_string = "Cambridge/Aachen")]
     CambridgeAachen,
+    /// Generalised kt with the given exponent of the transverse momentum
+    #[strum(to_string = "generalised kt")]
+    GenKt(f64),
 }
 
-#[derive(Copy, Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
 pub struct JetDefinition {
     pub algorithm: JetAlgorithm,
     pub radius: f64,
     pub min_pt: f64,
+    /// Maximum absolute jet rapidity
+    pub max_rap: Option<f64>,
+    /// Grooming applied to the clustered jets
+    pub grooming: Option<Grooming>,
 }
 
-pub fn cluster(event: &Event, jet_def: &JetDefinition) -> Vec<PseudoJet> {
-    let r = jet_def.radius;
-    let out = Vec::from_iter(event.out.iter().filter_map(|p| {
-        if p.is_parton() || HADRONS.contains(&p.id) {
+impl Default for JetDefinition {
+    fn default() -> Self {
+        Self {
+            algorithm: JetAlgorithm::AntiKt,
+            radius: 0.4,
+            min_pt: 20.,
+            max_rap: None,
+            grooming: None,
+        }
+    }
+}
+
+impl JetDefinition {
+    /// Check whether a jet passes the transverse momentum and rapidity cuts
+    pub fn accepts(&self, jet: &PseudoJet) -> bool {
+        f64::from(jet.pt()) > self.min_pt
+            && self
+                .max_rap
+                .is_none_or(|max_rap| f64::from(jet.rap()).abs() < max_rap)
+    }
+}
+
+const DEFAULT_MAX_RAP: f64 = 4.5;
+
+/// Radius for reclustering all constituents of a jet into a single jet
+const RECLUSTER_RADIUS: f64 = 1000.;
+
+/// Jet grooming technique together with its parameters
+#[derive(
+    Display, EnumIter, Copy, Clone, Debug, PartialEq, Deserialize, Serialize,
+)]
+pub enum Grooming {
+    #[strum(to_string = "soft drop")]
+    SoftDrop(SoftDrop),
+    #[strum(to_string = "trimming")]
+    Trimming(Trimming),
+    #[strum(to_string = "filtering")]
+    Filtering(Filtering),
+}
+
+impl Grooming {
+    /// Groom a jet given by the indices of its constituents in `event.out`
+    ///
+    /// Returns the groomed jet together with its remaining constituents,
+    /// or `None` if nothing is left of the jet.
+    fn groom(
+        &self,
+        event: &Event,
+        constituents: &[usize],
+        radius: f64,
+    ) -> Result<Option<(PseudoJet, Vec<usize>)>> {
+        match self {
+            Grooming::SoftDrop(soft_drop) => {
+                soft_drop.groom(event, constituents, radius)
+            }
+            Grooming::Trimming(trimming) => trimming.groom(event, constituents),
+            Grooming::Filtering(filtering) => {
+                filtering.groom(event, constituents)
+            }
+        }
+    }
+
+    /// Human-readable list of the grooming parameters
+    pub fn parameters(&self) -> String {
+        match self {
+            Grooming::SoftDrop(SoftDrop { z_cut, beta }) => {
+                format!("zcut = {z_cut}, β = {beta}")
+            }
+            Grooming::Trimming(Trimming { r_sub, f_cut }) => {
+                format!("Rsub = {r_sub}, fcut = {f_cut}")
+            }
+            Grooming::Filtering(Filtering { r_sub, n_subjets }) => {
+                format!("Rsub = {r_sub}, nsub = {n_subjets}")
+            }
+        }
+    }
+}
+
+/// Parameters of soft-drop grooming
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SoftDrop {
+    pub z_cut: f64,
+    /// Angular exponent β
+    pub beta: f64,
+}
+
+impl Default for SoftDrop {
+    fn default() -> Self {
+        Self {
+            z_cut: 0.1,
+            beta: 0.,
+        }
+    }
+}
+
+impl SoftDrop {
+    /// Check the soft-drop condition for the two branches of a jet
+    fn keeps(&self, [a, b]: [PseudoJet; 2], radius: f64) -> bool {
+        let [pt_a, pt_b] = [a, b].map(|j| f64::from(j.pt()));
+        let z = f64::min(pt_a, pt_b) / (pt_a + pt_b);
+        z > self.z_cut * (delta_r(&a, &b) / radius).powf(self.beta)
+    }
+
+    /// Groom a jet given by the indices of its constituents in `event.out`
+    ///
+    /// The constituents are reclustered with the Cambridge/Aachen
+    /// algorithm. Then the softer branch is dropped until the two
+    /// branches of the remaining jet satisfy the soft-drop condition.
+    fn groom(
+        &self,
+        event: &Event,
+        constituents: &[usize],
+        radius: f64,
+    ) -> Result<Option<(PseudoJet, Vec<usize>)>> {
+        let tree = ClusterTree::new(
+            event,
+            constituents,
+            JetAlgorithm::CambridgeAachen,
+            RECLUSTER_RADIUS,
+        )?;
+        let Some(&(mut node)) = tree.jets.first() else {
+            return Ok(None);
+        };
+        while let Some(branches) = tree.branches(node) {
+            let [a, b] = branches.map(|n| tree.momenta[n]);
+            if self.keeps([a, b], radius) {
+                break;
+            }
+            node = if a.pt2() > b.pt2() {
+                branches[0]
+            } else {
+                branches[1]
+            };
+        }
+        Ok(Some((tree.momenta[node], tree.constituents(node))))
+    }
+}
+
+/// Parameters of jet trimming
+///
+/// Subjets with less than a fraction `f_cut` of the jet transverse
+/// momentum are removed.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Trimming {
+    /// Radius for reclustering the constituents into subjets
+    pub r_sub: f64,
+    pub f_cut: f64,
+}
+
+impl Default for Trimming {
+    fn default() -> Self {
+        Self {
+            r_sub: 0.2,
+            f_cut: 0.03,
+        }
+    }
+}
+
+impl Trimming {
+    fn groom(
+        &self,
+        event: &Event,
+        constituents: &[usize],
+    ) -> Result<Option<(PseudoJet, Vec<usize>)>> {
+        let subjets = subjets(event, constituents, self.r_sub)?;
+        let Some(jet) = subjets
+            .iter()
+            .map(|(subjet, _)| *subjet)
+            .reduce(|a, b| a + b)
+        else {
+            return Ok(None);
+        };
+        let min_pt = self.f_cut * f64::from(jet.pt());
+        Ok(combine(
+            subjets
+                .into_iter()
+                .filter(|(subjet, _)| f64::from(subjet.pt()) > min_pt),
+        ))
+    }
+}
+
+/// Parameters of jet filtering
+///
+/// Only the `n_subjets` hardest subjets are kept.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Filtering {
+    /// Radius for reclustering the constituents into subjets
+    pub r_sub: f64,
+    pub n_subjets: usize,
+}
+
+impl Default for Filtering {
+    fn default() -> Self {
+        Self {
+            r_sub: 0.3,
+            n_subjets: 3,
+        }
+    }
+}
+
+impl Filtering {
+    fn groom(
+        &self,
+        event: &Event,
+        constituents: &[usize],
+    ) -> Result<Option<(PseudoJet, Vec<usize>)>> {
+        let mut subjets = subjets(event, constituents, self.r_sub)?;
+        subjets.sort_by_key(|(jet, _)| std::cmp::Reverse(jet.pt2()));
+        Ok(combine(subjets.into_iter().take(self.n_subjets)))
+    }
+}
+
+/// Recluster the constituents of a jet with the Cambridge/Aachen
+/// algorithm and radius `r_sub`
+///
+/// Each subjet is returned together with the indices of its
+/// constituents in `event.out`.
+fn subjets(
+    event: &Event,
+    constituents: &[usize],
+    r_sub: f64,
+) -> Result<Vec<(PseudoJet, Vec<usize>)>> {
+    let tree = ClusterTree::new(
+        event,
+        constituents,
+        JetAlgorithm::CambridgeAachen,
+        r_sub,
+    )?;
+    Ok(Vec::from_iter(tree.jets.iter().map(|&node| {
+        (tree.momenta[node], tree.constituents(node))
+    })))
+}
+
+/// Combine (sub)jets and their constituents into a single jet
+fn combine(
+    jets: impl IntoIterator<Item = (PseudoJet, Vec<usize>)>,
+) -> Option<(PseudoJet, Vec<usize>)> {
+    jets.into_iter().reduce(|(a, mut constituents), (b, more)| {
+        constituents.extend(more);
+        (a + b, constituents)
+    })
+}
+
+/// A node in the recombination tree of a clustering
+#[derive(Copy, Clone, Debug)]
+enum ClusterNode {
+    /// An input particle, given by its index in `event.out`
+    Particle(usize),
+    /// The combination of two earlier nodes
+    Merge([usize; 2]),
+}
+
+/// The recombination tree of a clustering by jetty
+///
+/// Nodes are referred to by their index. The momentum of each node is
+/// taken verbatim from jetty, so a merged node is exactly the sum of
+/// its two branches.
+#[derive(Clone, Debug, Default)]
+struct ClusterTree {
+    momenta: Vec<PseudoJet>,
+    nodes: Vec<ClusterNode>,
+    /// Nodes that were promoted to jets, in clustering order
+    jets: Vec<usize>,
+    /// Number of input particles, i.e. the leading `Particle` nodes
+    ninputs: usize,
+    /// Node created by a merge or promoted to a jet in each step
+    steps: Vec<usize>,
+}
+
+impl ClusterTree {
+    /// Cluster the particles `event.out[i]` for all `i` in `inputs`
+    ///
+    /// Fails if a pseudojet reported by jetty does not match any of
+    /// the proto-jets tracked here.
+    fn new(
+        event: &Event,
+        inputs: &[usize],
+        algorithm: JetAlgorithm,
+        radius: f64,
+    ) -> Result<Self> {
+        let mut tree = Self {
+            momenta: Vec::from_iter(
+                inputs.iter().map(|&i| PseudoJet::from(event.out[i].p)),
+            ),
+            nodes: Vec::from_iter(
+                inputs.iter().map(|&i| ClusterNode::Particle(i)),
+            ),
+            jets: Vec::new(),
+            ninputs: inputs.len(),
+            steps: Vec::new(),
+        };
+        // nodes that have not been merged yet, by momentum
+        let mut active: HashMap<PseudoJet, Vec<usize>> = HashMap::new();
+        for (node, p) in tree.momenta.iter().enumerate() {
+            active.entry(*p).or_default().push(node);
+        }
+        fn take(
+            active: &mut HashMap<PseudoJet, Vec<usize>>,
+            p: &PseudoJet,
+        ) -> Result<usize> {
+            active
+                .get_mut(p)
+                .and_then(Vec::pop)
+                .ok_or_else(|| anyhow!("jetty reports an unknown pseudojet"))
+        }
+        let history = algorithm.history(tree.momenta.clone(), radius);
+        for step in history {
+            match step {
+                jetty::ClusterStep::Combine([a, b]) => {
+                    let branches =
+                        [take(&mut active, &a)?, take(&mut active, &b)?];
+                    let merged = a + b;
+                    let node = tree.nodes.len();
+                    active.entry(merged).or_default().push(node);
+                    tree.momenta.push(merged);
+                    tree.nodes.push(ClusterNode::Merge(branches));
+                    tree.steps.push(node);
+                }
+                jetty::ClusterStep::Jet(jet) => {
+                    let node = take(&mut active, &jet)?;
+                    tree.jets.push(node);
+                    tree.steps.push(node);
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    /// The two branches that were merged into `node`, if any
+    fn branches(&self, node: usize) -> Option<[usize; 2]> {
+        match self.nodes[node] {
+            ClusterNode::Merge(branches) => Some(branches),
+            ClusterNode::Particle(_) => None,
+        }
+    }
+
+    /// Indices in `event.out` of the particles that make up `node`
+    fn constituents(&self, node: usize) -> Vec<usize> {
+        let mut constituents = Vec::new();
+        let mut todo = vec![node];
+        while let Some(node) = todo.pop() {
+            match self.nodes[node] {
+                ClusterNode::Particle(idx) => constituents.push(idx),
+                ClusterNode::Merge(branches) => todo.extend(branches),
+            }
+        }
+        constituents.sort_unstable();
+        constituents
+    }
+}
+
+impl JetAlgorithm {
+    /// Start clustering `inputs` with jetty
+    fn history(
+        &self,
+        inputs: Vec<PseudoJet>,
+        radius: f64,
+    ) -> ClusterHistory<'static> {
+        match self {
+            JetAlgorithm::AntiKt => {
+                ClusterHistory::new(inputs, anti_kt_f(radius))
+            }
+            JetAlgorithm::Kt => ClusterHistory::new(inputs, kt_f(radius)),
+            JetAlgorithm::CambridgeAachen => {
+                ClusterHistory::new(inputs, cambridge_aachen_f(radius))
+            }
+            JetAlgorithm::GenKt(p) => {
+                ClusterHistory::new(inputs, gen_kt_f(radius, *p))
+            }
+        }
+    }
+}
+
+/// Distance between two jets in the y-φ plane
+fn delta_r(a: &PseudoJet, b: &PseudoJet) -> f64 {
+    let dy = f64::from(a.rap()) - f64::from(b.rap());
+    let dphi = delta_phi(a.phi().into(), b.phi().into());
+    (dy * dy + dphi * dphi).sqrt()
+}
+
+/// Exclusive kt clustering of `particles` into at most `n` subjets
+pub fn exclusive_subjets(particles: &[PseudoJet], n: usize) -> Vec<PseudoJet> {
+    let mut subjets = particles.to_vec();
+    let dist = |a: &PseudoJet, b: &PseudoJet| {
+        f64::from(a.pt2().min(b.pt2())) * delta_r(a, b).powi(2)
+    };
+    while subjets.len() > n.max(1) {
+        let mut closest = (0, 1);
+        let mut min_dist = f64::INFINITY;
+        for (i, a) in subjets.iter().enumerate() {
+            for (j, b) in subjets.iter().enumerate().skip(i + 1) {
+                let d = dist(a, b);
+                if d < min_dist {
+                    min_dist = d;
+                    closest = (i, j);
+                }
+            }
+        }
+        let (i, j) = closest;
+        // j > i, so removing j first leaves i unchanged
+        let b = subjets.swap_remove(j);
+        let a = subjets.swap_remove(i);
+        subjets.push(a + b);
+    }
+    subjets
+}
+
+/// N-subjettiness τ_N of a jet with the given constituents
+///
+/// The axes are the `n` exclusive kt subjets, and the normalisation is
+/// the scalar transverse momentum sum of the constituents times the jet
+/// radius. Jets with at most `n` constituents have τ_N = 0.
+pub fn n_subjettiness(
+    constituents: &[PseudoJet],
+    n: usize,
+    radius: f64,
+) -> f64 {
+    let axes = exclusive_subjets(constituents, n);
+    let pt_sum: f64 = constituents.iter().map(|p| f64::from(p.pt())).sum();
+    if pt_sum <= 0. {
+        return 0.;
+    }
+    let tau: f64 = constituents
+        .iter()
+        .map(|p| {
+            let min_dist = axes
+                .iter()
+                .map(|axis| delta_r(p, axis))
+                .fold(f64::INFINITY, f64::min);
+            f64::from(p.pt()) * min_dist
+        })
+        .sum();
+    tau / (pt_sum * radius)
+}
+
+fn is_jet_input(p: &Particle) -> bool {
+    p.is_parton() || HADRONS.contains(&p.id)
+}
+
+fn jet_inputs(event: &Event) -> Vec<[f64; 4]> {
+    Vec::from_iter(event.out.iter().filter_map(|p| {
+        if is_jet_input(p) {
             Some(p.p)
         } else {
             None
         }
-    }));
-    let pt_cut = |p: PseudoJet| p.pt() > jet_def.min_pt;
+    }))
+}
+
+/// Indices of the jet inputs in `event.out`
+fn jet_input_indices(event: &Event) -> Vec<usize> {
+    Vec::from_iter(
+        event
+            .out
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| is_jet_input(p))
+            .map(|(idx, _)| idx),
+    )
+}
+
+pub fn cluster(event: &Event, jet_def: &JetDefinition) -> Vec<PseudoJet> {
+    if jet_def.grooming.is_some() {
+        return Vec::from_iter(
+            cluster_with_constituents(event, jet_def)
+                .into_iter()
+                .map(|(jet, _)| jet),
+        );
+    }
+    let r = jet_def.radius;
+    let out = jet_inputs(event);
+    let jet_cut = |p: PseudoJet| jet_def.accepts(&p);
     match jet_def.algorithm {
-        JetAlgorithm::AntiKt => out.cluster_if(anti_kt_f(r), pt_cut),
+        JetAlgorithm::AntiKt => out.cluster_if(anti_kt_f(r), jet_cut),
         JetAlgorithm::CambridgeAachen => {
-            out.cluster_if(cambridge_aachen_f(r), pt_cut)
+            out.cluster_if(cambridge_aachen_f(r), jet_cut)
+        }
+        JetAlgorithm::Kt => out.cluster_if(kt_f(r), jet_cut),
+        JetAlgorithm::GenKt(p) => out.cluster_if(gen_kt_f(r, p), jet_cut),
+    }
+}
+
+/// The full history of a sequential recombination
+///
+/// This is the clustering history reported by jetty, so replaying all
+/// steps gives the same jets as [cluster] without grooming.
+#[derive(Clone, Debug, Default)]
+pub struct ClusterSequence {
+    tree: ClusterTree,
+    /// Whether the jet promoted in a step passes the jet cuts
+    accepted: Vec<bool>,
+}
+
+/// The state of a clustering after a number of steps
+#[derive(Clone, Debug, Default)]
+pub struct ClusterState {
+    pub proto_jets: Vec<PseudoJet>,
+    pub jets: Vec<PseudoJet>,
+    pub last_merge: Option<[PseudoJet; 2]>,
+}
+
+impl ClusterSequence {
+    pub fn len(&self) -> usize {
+        self.tree.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.steps.is_empty()
+    }
+
+    /// Replay the first `nsteps` clustering steps
+    pub fn state_after(&self, nsteps: usize) -> ClusterState {
+        let tree = &self.tree;
+        // proto-jets by node, so that equal momenta are kept apart
+        let mut active = Vec::from_iter(0..tree.ninputs);
+        let mut state = ClusterState::default();
+        let steps = tree.steps.iter().zip(&self.accepted).take(nsteps);
+        for (&node, &accepted) in steps {
+            state.last_merge = None;
+            match tree.branches(node) {
+                Some(branches) if !active.contains(&node) => {
+                    active.retain(|n| !branches.contains(n));
+                    active.push(node);
+                    state.last_merge = Some(branches.map(|n| tree.momenta[n]));
+                }
+                _ => {
+                    active.retain(|&n| n != node);
+                    if accepted {
+                        state.jets.push(tree.momenta[node]);
+                    }
+                }
+            }
+        }
+        state.proto_jets =
+            Vec::from_iter(active.into_iter().map(|n| tree.momenta[n]));
+        state
+    }
+}
+
+/// Cluster, keeping track of the full sequence of recombinations
+pub fn cluster_sequence(
+    event: &Event,
+    jet_def: &JetDefinition,
+) -> ClusterSequence {
+    let tree = match ClusterTree::new(
+        event,
+        &jet_input_indices(event),
+        jet_def.algorithm,
+        jet_def.radius,
+    ) {
+        Ok(tree) => tree,
+        Err(err) => {
+            warn!("Failed to follow the clustering sequence: {err}");
+            return ClusterSequence::default();
+        }
+    };
+    let accepted = Vec::from_iter(
+        tree.steps
+            .iter()
+            .map(|&n| jet_def.accepts(&tree.momenta[n])),
+    );
+    ClusterSequence { tree, accepted }
+}
+
+/// Cluster, keeping track of the constituents of each jet
+///
+/// Each jet is returned together with the indices of its constituents
+/// in `event.out`. The jets are the same as the ones from [cluster],
+/// and groomed jets only keep the constituents that survive grooming.
+/// If the clustering history from jetty cannot be followed, a warning
+/// is logged and no jets are returned.
+pub fn cluster_with_constituents(
+    event: &Event,
+    jet_def: &JetDefinition,
+) -> Vec<(PseudoJet, Vec<usize>)> {
+    try_cluster_with_constituents(event, jet_def).unwrap_or_else(|err| {
+        warn!("Failed to track jet constituents: {err}");
+        Vec::new()
+    })
+}
+
+fn try_cluster_with_constituents(
+    event: &Event,
+    jet_def: &JetDefinition,
+) -> Result<Vec<(PseudoJet, Vec<usize>)>> {
+    let tree = ClusterTree::new(
+        event,
+        &jet_input_indices(event),
+        jet_def.algorithm,
+        jet_def.radius,
+    )?;
+    let mut jets = Vec::new();
+    for &node in &tree.jets {
+        if !jet_def.accepts(&tree.momenta[node]) {
+            continue;
+        }
+        let constituents = tree.constituents(node);
+        let jet = match jet_def.grooming {
+            Some(grooming) => {
+                grooming.groom(event, &constituents, jet_def.radius)?
+            }
+            None => Some((tree.momenta[node], constituents)),
+        };
+        jets.extend(jet);
+    }
+    Ok(jets)
+}
+
+/// Radius of the area in the y-φ plane that the jets cover
+///
+/// Without grooming this is the radius of the jet definition. Groomed
+/// jets are smaller, and the largest distance of any remaining
+/// constituent from its jet axis is returned instead.
+pub fn jet_area_radius(
+    event: &Event,
+    jet_def: &JetDefinition,
+    jets: &[(PseudoJet, Vec<usize>)],
+) -> f64 {
+    if jet_def.grooming.is_none() || jets.is_empty() {
+        return jet_def.radius;
+    }
+    jets.iter()
+        .flat_map(|(jet, constituents)| {
+            constituents
+                .iter()
+                .map(|&idx| delta_r(jet, &PseudoJet::from(event.out[idx].p)))
+        })
+        .fold(0., f64::max)
+}
+
+/// Number of jets and how much of the input they capture
+#[derive(Copy, Clone, Default, Debug)]
+pub struct ClusterSummary {
+    pub njets: usize,
+    /// Scalar sum of the transverse momenta of all jet constituents
+    pub jet_pt: f64,
+    /// Scalar sum of the transverse momenta of all jet inputs
+    pub input_pt: f64,
+}
+
+impl ClusterSummary {
+    /// Summarise jets given together with the indices of their
+    /// constituents in `event.out`
+    pub fn new(event: &Event, jets: &[(PseudoJet, Vec<usize>)]) -> Self {
+        Self {
+            njets: jets.len(),
+            jet_pt: jets
+                .iter()
+                .flat_map(|(_, constituents)| constituents)
+                .map(|&idx| event.out[idx].pt)
+                .sum(),
+            input_pt: event
+                .out
+                .iter()
+                .filter(|p| is_jet_input(p))
+                .map(|p| p.pt)
+                .sum(),
+        }
+    }
+
+    /// Fraction of the input transverse momentum that ended up in jets
+    pub fn jet_fraction(&self) -> f64 {
+        if self.input_pt > 0. {
+            self.jet_pt / self.input_pt
+        } else {
+            0.
         }
-        JetAlgorithm::Kt => out.cluster_if(kt_f(r), pt_cut),
     }
 }
 
@@ -62,10 +727,17 @@ pub struct ClusterSettings {
     pub is_open: bool,
     pub clustering_enabled: bool,
     pub jet_def: JetDefinition,
+    #[serde(skip)]
+    pub show_sequence: bool,
+    #[serde(skip)]
+    pub sequence_step: usize,
+    /// Result of clustering the current event, outside of sequence mode
+    #[serde(skip)]
+    pub summary: Option<ClusterSummary>,
 }
 
 impl ClusterSettings {
-    pub(crate) fn changed(&mut self, ctx: &Context) -> bool {
+    pub(crate) fn changed(&mut self, ctx: &Context, nsteps: usize) -> bool {
         let mut changed = false;
 
         let mut is_open = self.is_open;
@@ -85,7 +757,15 @@ impl ClusterSettings {
                         egui::ComboBox::from_id_source("Jet algorithm")
                             .selected_text(jet_def.algorithm.to_string())
                             .show_ui(ui, |ui| {
-                                for algo in JetAlgorithm::iter() {
+                                for mut algo in JetAlgorithm::iter() {
+                                    // keep the exponent when re-selecting
+                                    if let (
+                                        JetAlgorithm::GenKt(p),
+                                        JetAlgorithm::GenKt(current),
+                                    ) = (&mut algo, jet_def.algorithm)
+                                    {
+                                        *p = current;
+                                    }
                                     changed |= ui
                                         .selectable_value(
                                             &mut jet_def.algorithm,
@@ -97,6 +777,13 @@ impl ClusterSettings {
                             });
                         ui.label("Jet algorithm");
                     });
+                    if let JetAlgorithm::GenKt(p) = &mut jet_def.algorithm {
+                        ui.horizontal(|ui| {
+                            changed |=
+                                ui.add(DragValue::new(p).speed(0.01)).changed();
+                            ui.label("Exponent of transverse momentum");
+                        });
+                    }
                     ui.horizontal(|ui| {
                         changed |= ui
                             .add(
@@ -111,9 +798,304 @@ impl ClusterSettings {
                             .changed();
                         ui.label("Minimum jet transverse momentum");
                     });
+                    ui.horizontal(|ui| {
+                        let mut rap_cut = jet_def.max_rap.is_some();
+                        changed |= ui.checkbox(&mut rap_cut, "").changed();
+                        if rap_cut {
+                            let max_rap =
+                                jet_def.max_rap.get_or_insert(DEFAULT_MAX_RAP);
+                            changed |= ui
+                                .add(
+                                    DragValue::new(max_rap)
+                                        .speed(0.01)
+                                        .clamp_range(0.0..=f64::INFINITY),
+                                )
+                                .changed();
+                        } else {
+                            jet_def.max_rap = None;
+                        }
+                        ui.label("Maximum absolute jet rapidity");
+                    });
+                    ui.horizontal(|ui| {
+                        let selected = jet_def
+                            .grooming
+                            .map_or("none".to_owned(), |g| g.to_string());
+                        egui::ComboBox::from_id_source("Grooming")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut jet_def.grooming,
+                                        None,
+                                        "none",
+                                    )
+                                    .changed();
+                                for grooming in Grooming::iter() {
+                                    // keep the parameters when re-selecting
+                                    let is_current =
+                                        jet_def.grooming.is_some_and(|g| {
+                                            discriminant(&g)
+                                                == discriminant(&grooming)
+                                        });
+                                    let clicked = ui
+                                        .selectable_label(
+                                            is_current,
+                                            grooming.to_string(),
+                                        )
+                                        .clicked();
+                                    if clicked && !is_current {
+                                        jet_def.grooming = Some(grooming);
+                                        changed = true;
+                                    }
+                                }
+                            });
+                        ui.label("Grooming");
+                    });
+                    match &mut jet_def.grooming {
+                        Some(Grooming::SoftDrop(soft_drop)) => {
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(&mut soft_drop.z_cut)
+                                            .speed(0.001)
+                                            .clamp_range(0.0..=0.5),
+                                    )
+                                    .changed();
+                                ui.label("zcut");
+                            });
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(&mut soft_drop.beta)
+                                            .speed(0.01)
+                                            .clamp_range(0.0..=f64::INFINITY),
+                                    )
+                                    .changed();
+                                ui.label("β");
+                            });
+                        }
+                        Some(Grooming::Trimming(trimming)) => {
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(&mut trimming.r_sub)
+                                            .speed(0.01)
+                                            .clamp_range(0.01..=f64::INFINITY),
+                                    )
+                                    .changed();
+                                ui.label("Subjet radius Rsub");
+                            });
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(&mut trimming.f_cut)
+                                            .speed(0.001)
+                                            .clamp_range(0.0..=1.0),
+                                    )
+                                    .changed();
+                                ui.label("Minimum pT fraction fcut");
+                            });
+                        }
+                        Some(Grooming::Filtering(filtering)) => {
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(&mut filtering.r_sub)
+                                            .speed(0.01)
+                                            .clamp_range(0.01..=f64::INFINITY),
+                                    )
+                                    .changed();
+                                ui.label("Subjet radius Rsub");
+                            });
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        DragValue::new(
+                                            &mut filtering.n_subjets,
+                                        )
+                                        .clamp_range(1..=usize::MAX),
+                                    )
+                                    .changed();
+                                ui.label("Number of kept subjets");
+                            });
+                        }
+                        None => {}
+                    }
+                    changed |= ui
+                        .checkbox(
+                            &mut self.show_sequence,
+                            "Step through clustering sequence",
+                        )
+                        .changed();
+                    if self.show_sequence {
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(
+                                    &mut self.sequence_step,
+                                    0..=nsteps,
+                                )
+                                .text("Step"),
+                            )
+                            .changed();
+                    }
+                    if let Some(summary) = self.summary {
+                        ui.separator();
+                        let fraction = summary.jet_fraction();
+                        ui.label(format!(
+                            "{} jets with {:.1}% of the input pT, \
+                             {:.1}% unclustered",
+                            summary.njets,
+                            100. * fraction,
+                            100. * (1. - fraction),
+                        ));
+                    }
                 })
             });
         self.is_open = is_open;
         changed
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use event_file_reader::EventFileReader;
+
+    use super::*;
+
+    fn showered_events() -> Vec<Event> {
+        let reader = EventFileReader::new("examples/showered.hepmc.zst")
+            .expect("Failed to open example events");
+        Vec::from_iter(reader.take(10).map(|event| {
+            Event::from(event.expect("Failed to read example event"))
+        }))
+    }
+
+    fn assert_constituents_sum_to_jet(jet_def: &JetDefinition) {
+        for event in showered_events() {
+            let clustered = cluster_with_constituents(&event, jet_def);
+            let jets = Vec::from_iter(clustered.iter().map(|(jet, _)| *jet));
+            assert_eq!(jets, cluster(&event, jet_def));
+            for (jet, constituents) in clustered {
+                let sum = constituents
+                    .iter()
+                    .map(|&idx| PseudoJet::from(event.out[idx].p))
+                    .reduce(|a, b| a + b)
+                    .expect("Jet without constituents");
+                let tolerance = 1e-9 * f64::from(jet.e());
+                for (a, b) in [
+                    (jet.e(), sum.e()),
+                    (jet.px(), sum.px()),
+                    (jet.py(), sum.py()),
+                    (jet.pz(), sum.pz()),
+                ] {
+                    assert!(f64::from(a - b).abs() < tolerance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constituents_sum_to_jet() {
+        assert_constituents_sum_to_jet(&JetDefinition {
+            min_pt: 5.,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn groomed_constituents_sum_to_jet() {
+        let groomings = [
+            Grooming::SoftDrop(Default::default()),
+            Grooming::Trimming(Default::default()),
+            Grooming::Filtering(Default::default()),
+        ];
+        for grooming in groomings {
+            assert_constituents_sum_to_jet(&JetDefinition {
+                min_pt: 5.,
+                grooming: Some(grooming),
+                ..Default::default()
+            });
+        }
+    }
+
+    #[test]
+    fn groomed_radius_within_jet_radius() {
+        let jet_def = JetDefinition {
+            min_pt: 5.,
+            grooming: Some(Grooming::SoftDrop(Default::default())),
+            ..Default::default()
+        };
+        for event in showered_events() {
+            let clustered = cluster_with_constituents(&event, &jet_def);
+            let r_jet = jet_area_radius(&event, &jet_def, &clustered);
+            assert!(r_jet <= jet_def.radius);
+        }
+    }
+
+    #[test]
+    fn duplicate_and_collinear_inputs() {
+        use particle_id::ParticleID;
+
+        let gluon = |p| Particle::new(ParticleID::new(21), p);
+        let event = Event {
+            out: vec![
+                gluon([50., 30., 40., 0.]),
+                gluon([50., 30., 40., 0.]),
+                gluon([50., 30., 40., 0.]),
+                // collinear with the first three
+                gluon([100., 60., 80., 0.]),
+                gluon([50., -30., -40., 0.]),
+            ],
+            ..Default::default()
+        };
+        let algorithms = [
+            JetAlgorithm::AntiKt,
+            JetAlgorithm::Kt,
+            JetAlgorithm::CambridgeAachen,
+            JetAlgorithm::GenKt(0.5),
+        ];
+        for algorithm in algorithms {
+            let jet_def = JetDefinition {
+                algorithm,
+                min_pt: 0.,
+                ..Default::default()
+            };
+            let mut clustered = cluster_with_constituents(&event, &jet_def);
+            let jets = Vec::from_iter(clustered.iter().map(|(jet, _)| *jet));
+            assert_eq!(jets, cluster(&event, &jet_def));
+            clustered.sort_by_key(|(_, constituents)| constituents.len());
+            let constituents =
+                Vec::from_iter(clustered.into_iter().map(|(_, c)| c));
+            assert_eq!(constituents, [vec![4], vec![0, 1, 2, 3]]);
+        }
+    }
+
+    #[test]
+    fn sequence_replays_clustering() {
+        let jet_def = JetDefinition::default();
+        for event in showered_events() {
+            let sequence = cluster_sequence(&event, &jet_def);
+            let state = sequence.state_after(sequence.len());
+            assert!(state.proto_jets.is_empty());
+            assert_eq!(state.jets, cluster(&event, &jet_def));
+        }
+    }
+
+    #[test]
+    fn sequence_keeps_equal_proto_jets() {
+        use particle_id::ParticleID;
+
+        let gluon = Particle::new(ParticleID::new(21), [50., 30., 40., 0.]);
+        let event = Event {
+            out: vec![gluon; 3],
+            ..Default::default()
+        };
+        let sequence = cluster_sequence(&event, &JetDefinition::default());
+        let state = sequence.state_after(1);
+        assert_eq!(state.proto_jets.len(), 2);
+        assert!(state.last_merge.is_some());
+        let state = sequence.state_after(sequence.len());
+        assert_eq!(state.jets.len(), 1);
+        assert!(state.proto_jets.is_empty());
+    }
+}
@@ -1,26 +1,172 @@
 use avery::event::Status;
+use serde::Serialize;
 
-use crate::particle::Particle;
+use crate::particle::{Particle, ParticleStatus};
 
-#[derive(Clone, PartialEq, PartialOrd, Debug, Default)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize)]
 pub struct Event {
     pub out: Vec<Particle>,
+    /// Incoming particles, usually the colliding beams or partons
+    pub incoming: Vec<Particle>,
+    /// Particles that are neither incoming nor outgoing,
+    /// e.g. decayed resonances
+    pub intermediate: Vec<Particle>,
+    pub info: EventInfo,
+}
+
+/// Additional information stored alongside the particles
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize)]
+pub struct EventInfo {
+    pub id: Option<i32>,
+    /// All event weights, starting with the nominal one
+    pub weights: Vec<Weight>,
+    pub mu_r: Option<f64>,
+    pub mu_f: Option<f64>,
+    pub mu_ps: Option<f64>,
+    pub alpha_s: Option<f64>,
+    pub alpha: Option<f64>,
+}
+
+/// A single event weight
+///
+/// Weights without a value are kept, so that the position of each
+/// weight in [EventInfo::weights] stays meaningful.
+#[derive(Clone, PartialEq, PartialOrd, Debug, Default, Serialize)]
+pub struct Weight {
+    pub value: Option<f64>,
+    pub name: Option<String>,
+}
+
+impl From<f64> for Weight {
+    fn from(value: f64) -> Self {
+        Self {
+            value: Some(value),
+            name: None,
+        }
+    }
+}
+
+impl EventInfo {
+    /// Primary event weight, e.g. XWGTUP in the LHEF format
+    pub fn weight(&self) -> Option<f64> {
+        self.weights.first().and_then(|w| w.value)
+    }
+
+    /// Additional weights, e.g. for scale or PDF variations
+    pub fn alternative_weights(&self) -> &[Weight] {
+        self.weights.get(1..).unwrap_or_default()
+    }
+}
+
+impl Event {
+    /// Missing transverse momentum `[px, py]`
+    ///
+    /// This is the negative vector sum of the transverse momenta of all
+    /// visible, i.e. non-neutrino, outgoing particles.
+    pub fn missing_pt(&self) -> [f64; 2] {
+        self.out
+            .iter()
+            .filter(|p| !p.is_neutrino())
+            .fold([0., 0.], |[px, py], p| [px - p.p[1], py - p.p[2]])
+    }
+
+    /// Scalar sum of the transverse momenta of all outgoing particles
+    pub fn ht(&self) -> f64 {
+        self.out.iter().map(|p| p.pt).sum()
+    }
+
+    /// Sum of the four-momenta of all visible outgoing particles
+    pub fn visible_momentum(&self) -> [f64; 4] {
+        self.out
+            .iter()
+            .filter(|p| !p.is_neutrino())
+            .fold([0.; 4], |sum, p| std::array::from_fn(|i| sum[i] + p.p[i]))
+    }
+}
+
+impl Event {
+    /// Convert an event, skipping malformed particles
+    ///
+    /// Also returns the number of incoming and outgoing particles that
+    /// were skipped because their id or momentum is missing.
+    pub fn from_avery(event: avery::Event) -> (Self, usize) {
+        let info = EventInfo {
+            id: event.id,
+            weights: Vec::from_iter(event.weights.into_iter().map(|w| {
+                Weight {
+                    value: w.weight,
+                    name: w.name,
+                }
+            })),
+            mu_r: event.scales.mu_r,
+            mu_f: event.scales.mu_f,
+            mu_ps: event.scales.mu_ps,
+            alpha_s: event.alpha_s,
+            alpha: event.alpha,
+        };
+        let mut nskipped = 0;
+        let mut out = Vec::new();
+        let mut incoming = Vec::new();
+        let mut intermediate = Vec::new();
+        for p in event.particles {
+            let (target, status) = match p.status {
+                Some(Status::Outgoing) => (&mut out, ParticleStatus::Outgoing),
+                Some(Status::Incoming) => {
+                    (&mut incoming, ParticleStatus::Incoming)
+                }
+                Some(_) => (&mut intermediate, ParticleStatus::Intermediate),
+                None => continue,
+            };
+            match (p.id, p.p) {
+                (Some(id), Some(p)) => {
+                    target.push(Particle::new(id, p).with_status(status))
+                }
+                _ => nskipped += 1,
+            }
+        }
+        (
+            Event {
+                out,
+                incoming,
+                intermediate,
+                info,
+            },
+            nskipped,
+        )
+    }
 }
 
-// TODO: treat errors
 impl From<avery::Event> for Event {
     fn from(event: avery::Event) -> Self {
-        let out = event
-            .particles
-            .into_iter()
-            .filter_map(|p| {
-                if p.status == Some(Status::Outgoing) {
-                    Some(Particle::new(p.id.unwrap(), p.p.unwrap()))
-                } else {
-                    None
-                }
-            })
-            .collect();
-        Event { out }
+        Self::from_avery(event).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use avery::event::WeightInfo;
+
+    use super::*;
+
+    #[test]
+    fn missing_nominal_weight() {
+        let event = avery::Event {
+            weights: vec![
+                WeightInfo::default(),
+                WeightInfo {
+                    weight: Some(2.),
+                    name: Some("MUR2".to_owned()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let info = Event::from(event).info;
+        assert_eq!(info.weight(), None);
+        let [alternative] = info.alternative_weights() else {
+            panic!("expected a single alternative weight");
+        };
+        assert_eq!(alternative.value, Some(2.));
+        assert_eq!(alternative.name.as_deref(), Some("MUR2"));
     }
 }
@@ -1,15 +1,17 @@
-// TODO: opacity
 use std::{borrow::Cow, collections::HashSet, io::Write};
 
 use anyhow::Result;
+use egui::Color32;
 use jetty::PseudoJet;
 
 use crate::{
+    export::{write_provenance, FigureSize, Provenance},
     particle::Particle,
-    plotter::{self, y_min_max, PlotKind},
+    plotter::{self, y_min_max, ParticleStyle, PlotKind},
     Event,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn export_asy(
     out: impl Write,
     event: &Event,
@@ -17,12 +19,18 @@ pub(crate) fn export_asy(
     r_jet: f64,
     kind: PlotKind,
     settings: &plotter::Settings,
+    size: FigureSize,
+    provenance: Option<&Provenance>,
 ) -> Result<()> {
     use PlotKind::*;
     //todo!("write common code");
     match kind {
-        YPhi => export_asy_y_phi(out, event, jets, r_jet, settings),
-        YLogPt => export_asy_y_logpt(out, event, jets, r_jet, settings),
+        YPhi => export_asy_y_phi(
+            out, event, jets, r_jet, settings, size, provenance,
+        ),
+        YLogPt => export_asy_y_logpt(
+            out, event, jets, r_jet, settings, size, provenance,
+        ),
     }
 }
 
@@ -32,44 +40,59 @@ pub(crate) fn export_asy_y_phi(
     jets: &[PseudoJet],
     r_jet: f64,
     settings: &plotter::Settings,
+    size: FigureSize,
+    provenance: Option<&Provenance>,
 ) -> Result<()> {
     out.write_all(HEADER)?;
+    write_size(&mut out, size)?;
     out.write_all(Y_PHI_HEADER)?;
-    let [y_min, y_max] = y_min_max(&event.out);
+    let rapidity = settings.rapidity;
+    let [y_min, y_max] = y_min_max(&event.out, rapidity);
+    if let Some(provenance) = provenance {
+        write_provenance(&mut out, "//", event, provenance)?;
+        writeln!(out, "// {rapidity} range: [{y_min}, {y_max}]")?;
+        writeln!(out, "// φ range: [-π - 0.1, π + 0.1]")?;
+    }
     writeln!(
         out,
         "real xmin = {y_min};
 real xmax = {y_max};"
     )?;
     let mut seen = HashSet::new();
-    let r = settings.jets.r() as f32 / u8::MAX as f32;
-    let g = settings.jets.g() as f32 / u8::MAX as f32;
-    let b = settings.jets.b() as f32 / u8::MAX as f32;
-    for jet in jets {
-        let y = jet.y();
+    for (idx, jet) in jets.iter().enumerate() {
+        let jet_pen = pen(settings.jet_colour(idx));
+        let y = rapidity.of_jet(jet);
         let phi = jet.phi();
-        writeln!(out, "for(int i = -1; i <= 1; ++i) {{
-   fill(shift(0, 2*i*pi) * jet_guide({y}, {phi}, {r_jet}), rgb({r},{g},{b}) + opacity(0.2));
-}}")?;
+        writeln!(
+            out,
+            "for(int i = -1; i <= 1; ++i) {{
+   fill(shift(0, 2*i*pi) * jet_guide({y}, {phi}, {r_jet}), {jet_pen});
+}}"
+        )?;
     }
     for particle in &event.out {
-        let Particle { id, y, phi, .. } = particle;
-        let style = settings.particles.get(id).unwrap();
-        let size = style.size;
+        let Particle { id, phi, .. } = particle;
+        let y = rapidity.of(particle);
+        let style = settings.particle_style(*id);
+        let size = settings.marker_size.of(particle, style.size);
         let shape = style.shape;
-        let r = style.colour.r() as f32 / u8::MAX as f32;
-        let g = style.colour.g() as f32 / u8::MAX as f32;
-        let b = style.colour.b() as f32 / u8::MAX as f32;
+        let filler = filler(&style);
         if seen.insert(id) {
             let name = id
                 .latex_symbol()
                 .map(Cow::Borrowed)
                 .unwrap_or_else(|| Cow::Owned(id.id().to_string()));
-            writeln!(out, "draw(({y:.3}, {phi:.3}), p=invisible, marker=marker(scale({size})*{shape}, FillDraw(fillpen=rgb({r:.3},{g:.3},{b:.3}))), legend=\"${name}$\");")?;
+            writeln!(out, "draw(({y:.3}, {phi:.3}), p=invisible, marker=marker(scale({size})*{shape}, {filler}), legend=\"${name}$\");")?;
         } else {
-            writeln!(out, "draw(({y:.3}, {phi:.3}), p=invisible, marker=marker(scale({size})*{shape}, FillDraw(fillpen=rgb({r:.3},{g:.3},{b:.3}))));")?;
+            writeln!(out, "draw(({y:.3}, {phi:.3}), p=invisible, marker=marker(scale({size})*{shape}, {filler}));")?;
         }
     }
+    writeln!(
+        out,
+        "clip((xmin,phimin)--(xmax,phimin)--(xmax,phimax)--(xmin,phimax)--cycle);
+xaxis(Label(\"${}$\",0.5),YEquals(phimin),xmin,xmax,LeftTicks);",
+        rapidity.latex()
+    )?;
     out.write_all(Y_PHI_AXIS)?;
     Ok(())
 }
@@ -80,8 +103,11 @@ pub(crate) fn export_asy_y_logpt(
     jets: &[PseudoJet],
     r_jet: f64,
     settings: &plotter::Settings,
+    size: FigureSize,
+    provenance: Option<&Provenance>,
 ) -> Result<()> {
-    let [y_min, y_max] = y_min_max(&event.out);
+    let rapidity = settings.rapidity;
+    let [y_min, y_max] = y_min_max(&event.out, rapidity);
     writeln!(
         out,
         "real xmin = {y_min};
@@ -114,6 +140,12 @@ real xmax = {y_max};"
     let ptmax = ptmax.powf(1.1);
 
     out.write_all(HEADER)?;
+    write_size(&mut out, size)?;
+    if let Some(provenance) = provenance {
+        write_provenance(&mut out, "//", event, provenance)?;
+        writeln!(out, "// {rapidity} range: [{y_min}, {y_max}]")?;
+        writeln!(out, "// pT range: [{ptmin:.3}, {ptmax:.3}] GeV")?;
+    }
     writeln!(
         out,
         "real ptmin = {ptmin:.3};
@@ -121,38 +153,36 @@ real ptmax = {ptmax:.3};
 scale(Linear,Log);"
     )?;
     let mut seen = HashSet::new();
-    let r = settings.jets.r() as f32 / u8::MAX as f32;
-    let g = settings.jets.g() as f32 / u8::MAX as f32;
-    let b = settings.jets.b() as f32 / u8::MAX as f32;
-    for jet in jets {
-        let y = jet.y();
+    for (idx, jet) in jets.iter().enumerate() {
+        let jet_pen = pen(settings.jet_colour(idx));
+        let y = rapidity.of_jet(jet);
         let pt = jet.pt();
         let y_min = y - r_jet;
         let y_max = y + r_jet;
-        writeln!(out, "fill(box(({y_min:.3}, log10(ptmin)), ({y_max:.3}, log10({pt:.3}))), rgb({r:.3},{g:.3},{b:.3}) + opacity(0.2));")?;
+        writeln!(out, "fill(box(({y_min:.3}, log10(ptmin)), ({y_max:.3}, log10({pt:.3}))), {jet_pen});")?;
     }
     for particle in &event.out {
         let logpt = particle.pt.log10();
-        let Particle { id, y, .. } = particle;
-        let style = settings.particles.get(id).unwrap();
-        let size = style.size;
+        let id = &particle.id;
+        let y = rapidity.of(particle);
+        let style = settings.particle_style(*id);
+        let size = settings.marker_size.of(particle, style.size);
         let shape = style.shape;
-        let r = style.colour.r() as f32 / u8::MAX as f32;
-        let g = style.colour.g() as f32 / u8::MAX as f32;
-        let b = style.colour.b() as f32 / u8::MAX as f32;
+        let filler = filler(&style);
         if seen.insert(id) {
             let name = id
                 .latex_symbol()
                 .map(Cow::Borrowed)
                 .unwrap_or_else(|| Cow::Owned(id.id().to_string()));
-            writeln!(out, "draw(({y:.3}, {logpt:.3}), p=invisible, marker=marker(scale({size})*{shape}, FillDraw(fillpen=rgb({r:.3},{g:.3},{b:.3}))), legend=\"${name}$\");")?;
+            writeln!(out, "draw(({y:.3}, {logpt:.3}), p=invisible, marker=marker(scale({size})*{shape}, {filler}), legend=\"${name}$\");")?;
         } else {
-            writeln!(out, "draw(({y:.3}, {logpt:.3}), p=invisible, marker=marker(scale({size})*{shape}, FillDraw(fillpen=rgb({r:.3},{g:.3},{b:.3}))));")?;
+            writeln!(out, "draw(({y:.3}, {logpt:.3}), p=invisible, marker=marker(scale({size})*{shape}, {filler}));")?;
         }
     }
+    let label = rapidity.latex();
     writeln!(
         out,
-        r#"xaxis(Label("$y$",0.5),YEquals(ptmin),xmin,xmax,LeftTicks);
+        r#"xaxis(Label("${label}$",0.5),YEquals(ptmin),xmin,xmax,LeftTicks);
 xaxis(YEquals(ptmax),xmin,xmax,RightTicks("%"));
 yaxis(Label("$p_\perp\,$[GeV]",0.5),XEquals(xmin),ptmin,ptmax,RightTicks);
 yaxis(XEquals(xmax),ptmin,ptmax,LeftTicks("%"));
@@ -163,13 +193,110 @@ add(legend(invisible),(3.5, log10(ptmin) + 0.9*log10(ptmax/ptmin)));
     Ok(())
 }
 
+/// Asymptote pen with the colour and opacity of `colour`
+fn pen(colour: Color32) -> String {
+    let [r, g, b, a] = colour.to_srgba_unmultiplied();
+    let [r, g, b, a] = [r, g, b, a].map(|c| c as f32 / u8::MAX as f32);
+    format!("rgb({r:.3},{g:.3},{b:.3}) + opacity({a:.3})")
+}
+
+/// Filltype of a marker with the colour and outline of `style`
+fn filler(style: &ParticleStyle) -> String {
+    let fill = pen(style.colour);
+    if style.outline_width > 0. {
+        let outline = pen(style.outline);
+        let width = style.outline_width;
+        format!(
+            "FillDraw(fillpen={fill}, drawpen={outline} + linewidth({width}))"
+        )
+    } else {
+        format!("Fill({fill})")
+    }
+}
+
+/// Set the picture size, so that text is not rescaled when the figure
+/// is included in a document
+fn write_size(mut out: impl Write, size: FigureSize) -> Result<()> {
+    let [width, height] = size.mm;
+    writeln!(out, "size({width}mm,{height}mm,IgnoreAspect);")?;
+    Ok(())
+}
+
 const HEADER: &[u8] = include_bytes!("header.asy");
 const Y_PHI_HEADER: &[u8] = include_bytes!("y_phi.asy");
 
-const Y_PHI_AXIS: &[u8] =  br#"clip((xmin,phimin)--(xmax,phimin)--(xmax,phimax)--(xmin,phimax)--cycle);
-xaxis(Label("$y$",0.5),YEquals(phimin),xmin,xmax,LeftTicks);
-xaxis(YEquals(phimax),xmin,xmax,RightTicks("%"));
+const Y_PHI_AXIS: &[u8] =  br#"xaxis(YEquals(phimax),xmin,xmax,RightTicks("%"));
 yaxis(Label("$\phi$",0.5),XEquals(xmin),phimin,phimax,RightTicks(phi_label, Step=pi/2,step=pi/8));
 yaxis(XEquals(xmax),phimin,phimax,LeftTicks("%",Step=pi/4,step=pi/8));
 add(legend(invisible),(3.5,2.6));
 "#;
+
+#[cfg(test)]
+mod tests {
+    use particle_id::ParticleID;
+
+    use super::*;
+    use crate::plotter::Rapidity;
+
+    #[test]
+    fn export_unstyled_particle() {
+        // heavy neutral lepton without a default style
+        let id = ParticleID::new(9900012);
+        let settings = plotter::Settings::default();
+        assert!(!settings.particles.contains_key(&id));
+        let event = Event {
+            out: vec![Particle::new(id, [50., 10., 20., 30.])],
+            ..Default::default()
+        };
+        for kind in [PlotKind::YPhi, PlotKind::YLogPt] {
+            let mut out = Vec::new();
+            export_asy(
+                &mut out,
+                &event,
+                &[],
+                0.4,
+                kind,
+                &settings,
+                FigureSize::default(),
+                None,
+            )
+            .unwrap();
+            assert!(String::from_utf8(out).unwrap().contains("9900012"));
+        }
+    }
+
+    #[test]
+    fn export_pseudorapidity() {
+        let settings = plotter::Settings {
+            rapidity: Rapidity::Eta,
+            ..Default::default()
+        };
+        // massive particle, so that y and η differ
+        let particle = Particle::new(ParticleID::new(11), [50., 10., 20., 30.]);
+        let [y, eta] =
+            [particle.y, particle.eta()].map(|y| format!("({y:.3}, "));
+        assert_ne!(y, eta);
+        let event = Event {
+            out: vec![particle],
+            ..Default::default()
+        };
+        for kind in [PlotKind::YPhi, PlotKind::YLogPt] {
+            let mut out = Vec::new();
+            export_asy(
+                &mut out,
+                &event,
+                &[],
+                0.4,
+                kind,
+                &settings,
+                FigureSize::default(),
+                None,
+            )
+            .unwrap();
+            let asy = String::from_utf8(out).unwrap();
+            assert!(asy.contains(&eta));
+            assert!(!asy.contains(&y));
+            assert!(asy.contains(r#"Label("$\eta$",0.5)"#));
+        }
+    }
+}
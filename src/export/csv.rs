@@ -0,0 +1,91 @@
+use std::{borrow::Cow, io::Write};
+
+use anyhow::Result;
+use jetty::PseudoJet;
+
+use crate::{
+    particle::{delta_phi, particle_name},
+    Event,
+};
+
+pub(crate) fn export_csv(
+    mut out: impl Write,
+    event: &Event,
+    jets: &[PseudoJet],
+) -> Result<()> {
+    writeln!(out, "id,name,E,px,py,pz,y,phi,pt")?;
+    for particle in &event.out {
+        let id = particle.id.id();
+        let name = particle_name(particle.id);
+        let name = quote(&name);
+        let [e, px, py, pz] = particle.p;
+        let y = particle.y;
+        let phi = particle.phi;
+        let pt = particle.pt;
+        writeln!(out, "{id},{name},{e},{px},{py},{pz},{y},{phi},{pt}")?;
+    }
+    for jet in jets {
+        writeln!(
+            out,
+            "jet,jet,{},{},{},{},{},{},{}",
+            jet.e(),
+            jet.px(),
+            jet.py(),
+            jet.pz(),
+            jet.rap(),
+            delta_phi(jet.phi().into(), 0.),
+            jet.pt()
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote a field if it contains separators, quotes, or line breaks
+fn quote(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use particle_id::ParticleID;
+
+    use super::*;
+    use crate::particle::Particle;
+
+    #[test]
+    fn quote_nucleus_name() {
+        let pb208 = ParticleID::new(1000822080);
+        let event = Event {
+            out: vec![Particle::new(pb208, [200_000., 10., 20., 190_000.])],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        export_csv(&mut out, &event, &[]).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("1000822080,\"nucleus (Z = 82, A = 208)\","));
+        assert_eq!(row.split(',').count(), 10);
+    }
+
+    #[test]
+    fn jet_phi_range() {
+        let jet = PseudoJet::from([10., -3., -4., 0.]);
+        let mut out = Vec::new();
+        export_csv(&mut out, &Event::default(), &[jet]).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        let phi: f64 = row.split(',').nth(7).unwrap().parse().unwrap();
+        assert!((phi - (-4f64).atan2(-3.)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn quote_fields() {
+        assert_eq!(quote("jet"), "jet");
+        assert_eq!(quote("a,b"), "\"a,b\"");
+        assert_eq!(quote("a\"b"), "\"a\"\"b\"");
+    }
+}
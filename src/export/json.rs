@@ -0,0 +1,49 @@
+use std::io::Write;
+
+use anyhow::Result;
+use jetty::PseudoJet;
+use serde::Serialize;
+
+use crate::{clustering::JetDefinition, particle::delta_phi, Event};
+
+#[derive(Serialize)]
+struct JsonEvent<'a> {
+    #[serde(flatten)]
+    event: &'a Event,
+    jets: Vec<JsonJet>,
+    jet_definition: Option<JetDefinition>,
+}
+
+#[derive(Serialize)]
+struct JsonJet {
+    p: [f64; 4],
+    y: f64,
+    phi: f64,
+    pt: f64,
+}
+
+impl From<&PseudoJet> for JsonJet {
+    fn from(jet: &PseudoJet) -> Self {
+        Self {
+            p: [jet.e(), jet.px(), jet.py(), jet.pz()].map(f64::from),
+            y: jet.rap().into(),
+            phi: delta_phi(jet.phi().into(), 0.),
+            pt: jet.pt().into(),
+        }
+    }
+}
+
+pub(crate) fn export_json(
+    out: impl Write,
+    event: &Event,
+    jets: &[PseudoJet],
+    jet_definition: Option<JetDefinition>,
+) -> Result<()> {
+    let event = JsonEvent {
+        event,
+        jets: jets.iter().map(JsonJet::from).collect(),
+        jet_definition,
+    };
+    serde_json::to_writer_pretty(out, &event)?;
+    Ok(())
+}
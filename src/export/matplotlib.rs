@@ -0,0 +1,200 @@
+use std::io::Write;
+
+use anyhow::Result;
+use egui::Color32;
+use jetty::PseudoJet;
+
+use crate::{
+    export::{write_provenance, FigureSize, Provenance},
+    particle::{particle_name, Particle},
+    plotter::{self, y_min_max, MarkerShape, PlotKind},
+    Event,
+};
+
+/// Write a standalone python script drawing the event with matplotlib
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn export_matplotlib(
+    mut out: impl Write,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+    size: FigureSize,
+    provenance: Option<&Provenance>,
+) -> Result<()> {
+    use PlotKind::*;
+    let rapidity = settings.rapidity;
+    let [y_min, y_max] = y_min_max(&event.out, rapidity);
+    let label = rapidity.latex();
+    writeln!(out, "#!/usr/bin/env python3")?;
+    if let Some(provenance) = provenance {
+        write_provenance(&mut out, "#", event, provenance)?;
+        writeln!(out, "# {rapidity} range: [{y_min}, {y_max}]")?;
+    }
+    out.write_all(HEADER)?;
+    let [width, height] = size.inches();
+    let dpi = size.dpi;
+    writeln!(
+        out,
+        "fig, ax = plt.subplots(figsize=({width:.3}, {height:.3}), dpi={dpi})"
+    )?;
+    match kind {
+        YPhi => {
+            for (idx, jet) in jets.iter().enumerate() {
+                let (colour, alpha) = colour(settings.jet_colour(idx));
+                let y = rapidity.of_jet(jet);
+                let phi = f64::from(jet.phi());
+                writeln!(
+                    out,
+                    "for shift in (-2*pi, 0, 2*pi):
+    ax.add_patch(Circle(({y:.3}, {phi:.3} + shift), {r_jet}, \
+facecolor=\"{colour}\", alpha={alpha:.3}, linewidth=0))"
+                )?;
+            }
+            write_particles(&mut out, event, settings, |p| p.phi)?;
+            writeln!(
+                out,
+                "ax.set_xlim({y_min}, {y_max})
+ax.set_ylim(-pi - 0.1, pi + 0.1)
+ax.set_yticks(
+    [-pi, -pi/2, 0, pi/2, pi],
+    [r\"$-\\pi$\", r\"$-\\pi/2$\", \"0\", r\"$\\pi/2$\", r\"$\\pi$\"],
+)
+ax.set_xlabel(r\"${label}$\")
+ax.set_ylabel(r\"$\\phi$\")"
+            )?;
+        }
+        YLogPt => {
+            let [pt_min, pt_max] = pt_range(event, jets);
+            for (idx, jet) in jets.iter().enumerate() {
+                let (colour, alpha) = colour(settings.jet_colour(idx));
+                let y = rapidity.of_jet(jet);
+                let pt = f64::from(jet.pt());
+                let y_start = y - r_jet;
+                let width = 2. * r_jet;
+                let height = pt - pt_min;
+                writeln!(
+                    out,
+                    "ax.add_patch(Rectangle(({y_start:.3}, {pt_min:.3}), \
+{width:.3}, {height:.3}, facecolor=\"{colour}\", alpha={alpha:.3}, \
+linewidth=0))"
+                )?;
+            }
+            write_particles(&mut out, event, settings, |p| p.pt)?;
+            writeln!(
+                out,
+                "ax.set_xlim({y_min}, {y_max})
+ax.set_yscale(\"log\")
+ax.set_ylim({pt_min:.3}, {pt_max:.3})
+ax.set_xlabel(r\"${label}$\")
+ax.set_ylabel(r\"$p_\\perp$ [GeV]\")"
+            )?;
+        }
+    }
+    out.write_all(FOOTER)?;
+    Ok(())
+}
+
+/// One scatter plot for each particle species
+fn write_particles(
+    mut out: impl Write,
+    event: &Event,
+    settings: &plotter::Settings,
+    vertical: impl Fn(&Particle) -> f64,
+) -> Result<()> {
+    let mut ids = Vec::new();
+    for particle in &event.out {
+        if !ids.contains(&particle.id) {
+            ids.push(particle.id);
+        }
+    }
+    for id in ids {
+        let style = settings.particle_style(id);
+        let particles = Vec::from_iter(event.out.iter().filter(|p| p.id == id));
+        let ys = Vec::from_iter(
+            particles
+                .iter()
+                .map(|p| format!("{:.3}", settings.rapidity.of(p))),
+        );
+        let vs = Vec::from_iter(
+            particles.iter().map(|p| format!("{:.3}", vertical(p))),
+        );
+        let sizes = Vec::from_iter(particles.iter().map(|p| {
+            // matplotlib sizes are areas in points²
+            let size = 2. * settings.marker_size.of(p, style.size);
+            format!("{:.1}", size * size)
+        }));
+        let (outline, _) = colour(style.outline);
+        let (colour, alpha) = colour(style.colour);
+        // the edge colour is the line colour of unfilled markers
+        let outline = match style.shape {
+            MarkerShape::Cross | MarkerShape::Plus => colour.clone(),
+            _ => outline,
+        };
+        let outline_width = style.outline_width;
+        let marker = marker(style.shape);
+        let name = particle_name(id).replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(
+            out,
+            "ax.scatter([{}], [{}], s=[{}], marker=\"{marker}\", \
+color=\"{colour}\", edgecolors=\"{outline}\", \
+linewidths={outline_width}, alpha={alpha:.3}, label=\"{name}\")",
+            ys.join(", "),
+            vs.join(", "),
+            sizes.join(", "),
+        )?;
+    }
+    Ok(())
+}
+
+/// Range of the logarithmic transverse momentum axis
+fn pt_range(event: &Event, jets: &[PseudoJet]) -> [f64; 2] {
+    let pts = event
+        .out
+        .iter()
+        .map(|p| p.pt)
+        .chain(jets.iter().map(|jet| jet.pt().into()));
+    let (pt_min, pt_max) = pts.fold((f64::MAX, 0.), |(min, max), pt| {
+        (f64::min(min, pt), f64::max(max, pt))
+    });
+    if pt_min > pt_max {
+        // some default values for empty events
+        [1., 10.]
+    } else {
+        [pt_min.powf(0.9), pt_max.powf(1.1)]
+    }
+}
+
+/// Hexadecimal matplotlib colour and opacity
+fn colour(colour: Color32) -> (String, f32) {
+    let [r, g, b, a] = colour.to_srgba_unmultiplied();
+    (format!("#{r:02x}{g:02x}{b:02x}"), a as f32 / u8::MAX as f32)
+}
+
+fn marker(shape: MarkerShape) -> &'static str {
+    use MarkerShape::*;
+    match shape {
+        Circle => "o",
+        Diamond => "D",
+        Square => "s",
+        Cross => "x",
+        Plus => "+",
+        Up => "^",
+        Down => "v",
+        Left => "<",
+        Right => ">",
+        Asterisk => "*",
+    }
+}
+
+const HEADER: &[u8] = br#"from math import pi
+
+import matplotlib.pyplot as plt
+from matplotlib.patches import Circle, Rectangle
+
+"#;
+
+const FOOTER: &[u8] = br#"ax.legend()
+plt.show()
+"#;
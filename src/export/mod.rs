@@ -1,16 +1,111 @@
 mod asy;
+mod csv;
+mod json;
+mod matplotlib;
+mod pdf;
+mod png;
+mod render;
+mod svg;
 
-use std::{fs::File, io::BufWriter, path::Path};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+};
 
 use anyhow::{Context, Result};
 use jetty::PseudoJet;
 
 use crate::{
-    export::asy::export_asy,
+    clustering::{cluster_with_constituents, jet_area_radius, JetDefinition},
+    export::{
+        asy::export_asy, csv::export_csv, json::export_json,
+        matplotlib::export_matplotlib, pdf::export_pdf, png::export_png,
+    },
     plotter::{self, ExportFormat, PlotKind},
     Event,
 };
 
+pub use svg::render_svg;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct ExportOptions {
+    /// Embed the figure in a standalone LaTeX document
+    pub(crate) standalone_latex: bool,
+    /// Document the event and plot ranges in comments
+    pub(crate) provenance: bool,
+    pub(crate) size: FigureSize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            standalone_latex: false,
+            provenance: true,
+            size: Default::default(),
+        }
+    }
+}
+
+const MM_PER_INCH: f32 = 25.4;
+const POINTS_PER_INCH: f32 = 72.;
+/// Maximum width and height of rasterised images in pixels
+const MAX_PIXELS: f32 = 16384.;
+
+/// Physical size and resolution of exported figures
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct FigureSize {
+    /// Width and height in millimetres
+    pub(crate) mm: [f32; 2],
+    /// Resolution of rasterised images in dots per inch
+    pub(crate) dpi: u32,
+}
+
+impl Default for FigureSize {
+    fn default() -> Self {
+        Self {
+            mm: [122., 90.],
+            dpi: 200,
+        }
+    }
+}
+
+impl FigureSize {
+    /// Width and height in inches
+    pub(crate) fn inches(&self) -> [f32; 2] {
+        self.mm.map(|len| len / MM_PER_INCH)
+    }
+
+    /// Width and height in PostScript points
+    pub(crate) fn points(&self) -> [u32; 2] {
+        self.inches()
+            .map(|len| (len * POINTS_PER_INCH).round() as u32)
+    }
+
+    /// Width and height in pixels at the chosen resolution
+    ///
+    /// Images that would exceed `MAX_PIXELS` in either direction are
+    /// scaled down, keeping the aspect ratio.
+    pub(crate) fn pixels(&self) -> [u32; 2] {
+        let pixels = self.inches().map(|len| len * self.dpi as f32);
+        let scale = f32::min(1., MAX_PIXELS / pixels[0].max(pixels[1]));
+        pixels.map(|len| (scale * len).round().clamp(1., MAX_PIXELS) as u32)
+    }
+}
+
+/// Information on how an exported figure was produced
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Provenance {
+    pub(crate) event_idx: usize,
+    pub(crate) jet_def: Option<JetDefinition>,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn export(
     path: &Path,
     event: &Event,
@@ -19,12 +114,207 @@ pub(crate) fn export(
     kind: PlotKind,
     format: ExportFormat,
     settings: &plotter::Settings,
+    options: &ExportOptions,
+    provenance: &Provenance,
 ) -> Result<()> {
     use ExportFormat::*;
-    let out = File::create(path)
-        .with_context(|| format!("Failed to open {path:?}"))?;
-    let out = BufWriter::new(out);
+    let mut event = event.clone();
+    event.out = settings.visible_particles(&event);
     match format {
-        Asymptote => export_asy(out, event, jets, r_jet, kind, settings),
+        Asymptote => {
+            let out = File::create(path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            let mut out = BufWriter::new(out);
+            let provenance = options.provenance.then_some(provenance);
+            if options.standalone_latex {
+                let mut asy = Vec::new();
+                export_asy(
+                    &mut asy,
+                    &event,
+                    jets,
+                    r_jet,
+                    kind,
+                    settings,
+                    options.size,
+                    provenance,
+                )?;
+                write_latex_asy(out, &asy)
+            } else {
+                export_asy(
+                    &mut out,
+                    &event,
+                    jets,
+                    r_jet,
+                    kind,
+                    settings,
+                    options.size,
+                    provenance,
+                )
+            }
+        }
+        Csv => {
+            let out = File::create(path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            export_csv(BufWriter::new(out), &event, jets)
+        }
+        Json => {
+            let out = File::create(path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            let out = BufWriter::new(out);
+            export_json(out, &event, jets, provenance.jet_def)
+        }
+        Matplotlib => {
+            let out = File::create(path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            let provenance = options.provenance.then_some(provenance);
+            export_matplotlib(
+                BufWriter::new(out),
+                &event,
+                jets,
+                r_jet,
+                kind,
+                settings,
+                options.size,
+                provenance,
+            )
+        }
+        Pdf => {
+            let out = File::create(path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            let out = BufWriter::new(out);
+            let size = options.size.points();
+            export_pdf(out, &event, jets, r_jet, kind, settings, size)
+        }
+        Png => export_png(
+            path,
+            &event,
+            jets,
+            r_jet,
+            kind,
+            settings,
+            options.size.pixels(),
+        ),
+    }
+}
+
+/// Document the event and jet definition in comments starting with `comment`
+fn write_provenance(
+    mut out: impl Write,
+    comment: &str,
+    event: &Event,
+    provenance: &Provenance,
+) -> Result<()> {
+    writeln!(out, "{comment} event number: {}", provenance.event_idx + 1)?;
+    if let Some(weight) = event.info.weight() {
+        writeln!(out, "{comment} event weight: {weight}")?;
+    }
+    if let Some(jet_def) = provenance.jet_def {
+        writeln!(
+            out,
+            "{comment} jets: {} algorithm, R = {}, pT > {} GeV",
+            jet_def.algorithm, jet_def.radius, jet_def.min_pt
+        )?;
+        if let Some(max_rap) = jet_def.max_rap {
+            writeln!(out, "{comment} jet rapidity: |y| < {max_rap}")?;
+        }
+        if let Some(grooming) = jet_def.grooming {
+            let parameters = grooming.parameters();
+            writeln!(out, "{comment} {grooming}: {parameters}")?;
+        }
+    } else {
+        writeln!(out, "{comment} jets: none")?;
     }
+    Ok(())
+}
+
+/// Export of all events into a directory
+#[derive(Debug)]
+pub(crate) struct BatchExport {
+    pub(crate) dir: PathBuf,
+    pub(crate) events: Vec<Event>,
+    pub(crate) jet_def: Option<JetDefinition>,
+    pub(crate) kind: PlotKind,
+    pub(crate) format: ExportFormat,
+    pub(crate) settings: plotter::Settings,
+    pub(crate) options: ExportOptions,
+    /// Set to stop the export before the next event
+    pub(crate) cancel: Arc<AtomicBool>,
 }
+
+impl BatchExport {
+    /// Export all events, reporting progress and errors through `s_msg`
+    ///
+    /// Returns `false` if the receiving end has hung up.
+    pub(crate) fn run(&self, s_msg: &Sender<String>) -> bool {
+        match self.try_run(s_msg) {
+            Ok(connected) => connected,
+            Err(err) => s_msg.send(format!("{err:#}")).is_ok(),
+        }
+    }
+
+    /// Export all events, reporting progress through `s_msg`
+    ///
+    /// Stops at the first event that fails to export. Returns
+    /// `Ok(false)` if the receiving end has hung up.
+    pub(crate) fn try_run(&self, s_msg: &Sender<String>) -> Result<bool> {
+        let nevents = self.events.len();
+        for (event_idx, event) in self.events.iter().enumerate() {
+            if self.cancel.load(Ordering::Relaxed) {
+                let msg = format!("Export cancelled after {event_idx} events");
+                return Ok(s_msg.send(msg).is_ok());
+            }
+            let msg = format!("Exporting event {}/{nevents}", event_idx + 1);
+            if s_msg.send(msg).is_err() {
+                return Ok(false);
+            }
+            let (jets, r_jet) = match &self.jet_def {
+                Some(jet_def) => {
+                    let clustered = cluster_with_constituents(event, jet_def);
+                    let r_jet = jet_area_radius(event, jet_def, &clustered);
+                    let jets = clustered.into_iter().map(|(jet, _)| jet);
+                    (Vec::from_iter(jets), r_jet)
+                }
+                None => (Vec::new(), 0.),
+            };
+            let name = format!("event_{event_idx}.{}", self.format.suffix());
+            let provenance = Provenance {
+                event_idx,
+                jet_def: self.jet_def,
+            };
+            export(
+                &self.dir.join(name),
+                event,
+                &jets,
+                r_jet,
+                self.kind,
+                self.format,
+                &self.settings,
+                &self.options,
+                &provenance,
+            )?;
+        }
+        let dir = &self.dir;
+        Ok(s_msg
+            .send(format!("Exported {nevents} events to {dir:?}"))
+            .is_ok())
+    }
+}
+
+fn write_latex_asy(mut out: impl Write, asy: &[u8]) -> Result<()> {
+    out.write_all(LATEX_ASY_HEADER)?;
+    out.write_all(asy)?;
+    out.write_all(LATEX_ASY_FOOTER)?;
+    Ok(())
+}
+
+const LATEX_ASY_HEADER: &[u8] = br"\documentclass{standalone}
+% compile with pdflatex -shell-escape
+\usepackage{amsmath}
+\usepackage[inline]{asymptote}
+\begin{document}
+\begin{asy}
+";
+
+const LATEX_ASY_FOOTER: &[u8] = br"\end{asy}
+\end{document}
+";
@@ -0,0 +1,36 @@
+use std::io::Write;
+
+use anyhow::Result;
+use jetty::PseudoJet;
+use usvg::{fontdb, TreeParsing, TreeTextToPath};
+
+use crate::{
+    export::svg::to_svg,
+    plotter::{self, PlotKind},
+    Event,
+};
+
+pub(crate) fn export_pdf(
+    mut out: impl Write,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+    size: [u32; 2],
+) -> Result<()> {
+    // page size in points
+    let [width, height] = size;
+    let svg = to_svg((width, height), event, jets, r_jet, kind, settings)?;
+    let mut tree = usvg::Tree::from_str(&svg, &Default::default())?;
+    // convert text to vector paths, so we don't depend on system fonts
+    let mut fonts = fontdb::Database::new();
+    fonts.load_font_data(FONT.to_vec());
+    fonts.set_sans_serif_family("DejaVu Sans");
+    tree.convert_text(&fonts);
+    let pdf = svg2pdf::convert_tree(&tree, Default::default());
+    out.write_all(&pdf)?;
+    Ok(())
+}
+
+const FONT: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use anyhow::Result;
+use jetty::PseudoJet;
+use plotters::prelude::*;
+
+use crate::{
+    export::render::render,
+    plotter::{self, PlotKind},
+    Event,
+};
+
+pub(crate) fn export_png(
+    path: &Path,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+    size: [u32; 2],
+) -> Result<()> {
+    let [width, height] = size;
+    let root = BitMapBackend::new(path, (width, height)).into_drawing_area();
+    render(&root, event, jets, r_jet, kind, settings)
+}
@@ -0,0 +1,189 @@
+use std::f64::consts::PI;
+
+use anyhow::Result;
+use jetty::PseudoJet;
+use plotters::{
+    coord::{CoordTranslate, Shift},
+    prelude::*,
+};
+
+use crate::{
+    particle::Particle,
+    plotter::{self, to_plotters_col, y_min_max, MarkerShape, PlotKind},
+    Event,
+};
+
+const PHI_MIN: f64 = -PI - 0.1;
+const PHI_MAX: f64 = PI + 0.1;
+const NUM_JET_SEGMENTS: usize = 48;
+
+/// Draw a plot with one of the `plotters` backends
+pub(super) fn render<DB>(
+    root: &DrawingArea<DB, Shift>,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+) -> Result<()>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    use PlotKind::*;
+    root.fill(&WHITE)?;
+    match kind {
+        YPhi => draw_y_phi(root, event, jets, r_jet, settings)?,
+        YLogPt => draw_y_logpt(root, event, jets, r_jet, settings)?,
+    }
+    root.present()?;
+    Ok(())
+}
+
+fn draw_y_phi<DB>(
+    root: &DrawingArea<DB, Shift>,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    settings: &plotter::Settings,
+) -> Result<()>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let rapidity = settings.rapidity;
+    let [y_min, y_max] = y_min_max(&event.out, rapidity);
+    let mut chart = ChartBuilder::on(root)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(y_min..y_max, PHI_MIN..PHI_MAX)?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc(rapidity.to_string())
+        .y_desc("φ")
+        .draw()?;
+
+    for (idx, jet) in jets.iter().enumerate() {
+        let jet_colour = to_plotters_col(settings.jet_colour(idx));
+        let y = rapidity.of_jet(jet);
+        let phi: f64 = jet.phi().into();
+        for shift in [-2. * PI, 0., 2. * PI] {
+            let outline = (0..NUM_JET_SEGMENTS).map(|i| {
+                let angle = 2. * PI * i as f64 / NUM_JET_SEGMENTS as f64;
+                (y + r_jet * angle.cos(), phi + shift + r_jet * angle.sin())
+            });
+            chart.draw_series(std::iter::once(Polygon::new(
+                outline.collect::<Vec<_>>(),
+                jet_colour.filled(),
+            )))?;
+        }
+    }
+
+    let coord = |p: &Particle| (rapidity.of(p), p.phi);
+    draw_markers(&mut chart, &event.out, coord, settings)?;
+    Ok(())
+}
+
+fn draw_y_logpt<DB>(
+    root: &DrawingArea<DB, Shift>,
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    settings: &plotter::Settings,
+) -> Result<()>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let rapidity = settings.rapidity;
+    let [y_min, y_max] = y_min_max(&event.out, rapidity);
+    let pts = event
+        .out
+        .iter()
+        .map(|p| p.pt)
+        .chain(jets.iter().map(|jet| jet.pt().into()));
+    let (pt_min, pt_max) = pts.fold((f64::MAX, 0.), |(min, max), pt| {
+        (f64::min(min, pt), f64::max(max, pt))
+    });
+    let (pt_min, pt_max) = if pt_min > pt_max {
+        // some default values for empty events
+        (1., 10.)
+    } else {
+        (pt_min.powf(0.9), pt_max.powf(1.1))
+    };
+    let mut chart = ChartBuilder::on(root)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(y_min..y_max, (pt_min..pt_max).log_scale())?;
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc(rapidity.to_string())
+        .y_desc("pT [GeV]")
+        .draw()?;
+
+    chart.draw_series(jets.iter().enumerate().map(|(idx, jet)| {
+        let jet_colour = to_plotters_col(settings.jet_colour(idx));
+        let y = rapidity.of_jet(jet);
+        let pt: f64 = jet.pt().into();
+        Rectangle::new(
+            [(y - r_jet, pt_min), (y + r_jet, pt)],
+            jet_colour.filled(),
+        )
+    }))?;
+
+    let coord = |p: &Particle| (rapidity.of(p), p.pt);
+    draw_markers(&mut chart, &event.out, coord, settings)?;
+    Ok(())
+}
+
+/// Draw a marker for each particle at the coordinates given by `coord`
+///
+/// Markers are drawn one shape after the other. Boxing the different
+/// element types would require a drawing backend that outlives
+/// everything.
+fn draw_markers<DB, CT>(
+    chart: &mut ChartContext<'_, DB, CT>,
+    particles: &[Particle],
+    coord: impl Fn(&Particle) -> (f64, f64),
+    settings: &plotter::Settings,
+) -> Result<()>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+    CT: CoordTranslate<From = (f64, f64)>,
+{
+    use MarkerShape as M;
+    let markers = Vec::from_iter(particles.iter().map(|particle| {
+        let style = settings.particle_style(particle.id);
+        let colour = to_plotters_col(style.colour).filled();
+        let size = settings.marker_size.of(particle, style.size).round() as i32;
+        (coord(particle), style.shape, colour, size)
+    }));
+    let with_shape = |shapes: &'static [MarkerShape]| {
+        markers
+            .iter()
+            .filter(move |(_, shape, _, _)| shapes.contains(shape))
+            .map(|&(coord, _, colour, size)| (coord, colour, size))
+    };
+    chart.draw_series(with_shape(&[M::Square]).map(
+        |(coord, colour, size)| {
+            EmptyElement::at(coord)
+                + Rectangle::new([(-size, -size), (size, size)], colour)
+        },
+    ))?;
+    chart.draw_series(with_shape(&[M::Up]).map(|(coord, colour, size)| {
+        TriangleMarker::new(coord, size, colour)
+    }))?;
+    chart.draw_series(with_shape(&[M::Cross, M::Plus, M::Asterisk]).map(
+        |(coord, colour, size)| Cross::new(coord, size, colour.stroke_width(2)),
+    ))?;
+    chart.draw_series(
+        with_shape(&[M::Circle, M::Diamond, M::Down, M::Left, M::Right])
+            .map(|(coord, colour, size)| Circle::new(coord, size, colour)),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use jetty::PseudoJet;
+use plotters::prelude::*;
+
+use crate::{
+    export::render::render,
+    plotter::{self, PlotKind},
+    Event,
+};
+
+// figure size in pixels
+const SVG_SIZE: (u32, u32) = (800, 600);
+
+/// Render a plot of an event to an SVG document
+///
+/// Jets are drawn with radius `r_jet`. Particles hidden by the
+/// transverse momentum thresholds in `settings` are left out.
+pub fn render_svg(
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+) -> Result<String> {
+    let mut event = event.clone();
+    event.out = settings.visible_particles(&event);
+    to_svg(SVG_SIZE, &event, jets, r_jet, kind, settings)
+}
+
+pub(super) fn to_svg(
+    size: (u32, u32),
+    event: &Event,
+    jets: &[PseudoJet],
+    r_jet: f64,
+    kind: PlotKind,
+    settings: &plotter::Settings,
+) -> Result<String> {
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, size).into_drawing_area();
+        render(&root, event, jets, r_jet, kind, settings)?;
+    }
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use particle_id::ParticleID;
+
+    use super::*;
+    use crate::particle::Particle;
+
+    #[test]
+    fn render_nucleus() {
+        let pb208 = ParticleID::new(1000822080);
+        let event = Event {
+            out: vec![Particle::new(pb208, [200_000., 10., 20., 190_000.])],
+            ..Default::default()
+        };
+        let settings = plotter::Settings::default();
+        for kind in [PlotKind::YPhi, PlotKind::YLogPt] {
+            render_svg(&event, &[], 0.4, kind, &settings).unwrap();
+        }
+    }
+}
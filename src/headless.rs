@@ -0,0 +1,173 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, mpsc::channel, Arc},
+    thread::spawn,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use strum::IntoEnumIterator;
+
+use crate::{
+    app::load_file,
+    clustering::{JetAlgorithm, JetDefinition},
+    export::{BatchExport, ExportOptions},
+    plotter::{self, ExportFormat, PlotKind},
+};
+
+const USAGE: &str =
+    "Usage: evil --export DIR [--format asy|csv|json|pdf|png|py] \
+[--kind yphi|ylogpt] [--jet-algorithm anti-kt|kt|ca|genkt:P] [--jet-radius R] \
+[--jet-min-pt PT] [--no-jets] FILES...";
+
+/// Export all events from the command line without opening a window
+#[derive(Debug)]
+pub struct HeadlessExport {
+    files: Vec<String>,
+    dir: PathBuf,
+    format: ExportFormat,
+    kind: PlotKind,
+    jet_def: Option<JetDefinition>,
+}
+
+impl HeadlessExport {
+    /// Parse the command line arguments, excluding the program name
+    ///
+    /// Returns `None` if there is no `--export` flag, i.e. the
+    /// graphical interface should be started.
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Option<Self>> {
+        let mut dir = None;
+        let mut format = ExportFormat::Asymptote;
+        let mut kind = PlotKind::YPhi;
+        let mut jet_def = Some(JetDefinition::default());
+        let mut files = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                files.push(arg);
+                continue;
+            }
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("Missing value for {arg}\n{USAGE}"))
+            };
+            match arg.as_str() {
+                "--export" => dir = Some(PathBuf::from(value()?)),
+                "--format" => format = parse_format(&value()?)?,
+                "--kind" => kind = parse_kind(&value()?)?,
+                "--jet-algorithm" => {
+                    let algorithm = parse_algorithm(&value()?)?;
+                    jet_def.get_or_insert_with(Default::default).algorithm =
+                        algorithm;
+                }
+                "--jet-radius" => {
+                    let radius =
+                        value()?.parse().context("Invalid jet radius")?;
+                    jet_def.get_or_insert_with(Default::default).radius =
+                        radius;
+                }
+                "--jet-min-pt" => {
+                    let min_pt = value()?
+                        .parse()
+                        .context("Invalid minimum jet transverse momentum")?;
+                    jet_def.get_or_insert_with(Default::default).min_pt =
+                        min_pt;
+                }
+                "--no-jets" => jet_def = None,
+                _ => bail!("Unknown option {arg}\n{USAGE}"),
+            }
+        }
+        let Some(dir) = dir else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            files,
+            dir,
+            format,
+            kind,
+            jet_def,
+        }))
+    }
+
+    /// Load all events and export them
+    pub fn run(self) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let (s_ev, r_ev) = channel();
+        let (s_msg, r_msg) = channel();
+        for file in &self.files {
+            load_file(file, &s_ev, &s_msg);
+            for msg in r_msg.try_iter().filter(|msg| !msg.is_empty()) {
+                eprintln!("{msg}");
+            }
+        }
+        drop(s_ev);
+        let events = Vec::from_iter(r_ev.try_iter());
+        if events.is_empty() {
+            bail!("No events to export");
+        }
+        let export = BatchExport {
+            dir: self.dir,
+            events,
+            jet_def: self.jet_def,
+            kind: self.kind,
+            format: self.format,
+            settings: plotter::Settings::default(),
+            options: ExportOptions::default(),
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+        let worker = spawn(move || export.try_run(&s_msg));
+        // the channel closes once the export has finished
+        for msg in r_msg {
+            eprintln!("{msg}");
+        }
+        worker
+            .join()
+            .map_err(|_| anyhow!("Export thread panicked"))?
+            .map(|_| ())
+    }
+}
+
+fn parse_format(format: &str) -> Result<ExportFormat> {
+    ExportFormat::iter()
+        .find(|f| f.suffix() == format)
+        .ok_or_else(|| anyhow!("Unknown export format {format}\n{USAGE}"))
+}
+
+fn parse_kind(kind: &str) -> Result<PlotKind> {
+    match kind {
+        "yphi" => Ok(PlotKind::YPhi),
+        "ylogpt" => Ok(PlotKind::YLogPt),
+        _ => bail!("Unknown plot kind {kind}\n{USAGE}"),
+    }
+}
+
+fn parse_algorithm(algorithm: &str) -> Result<JetAlgorithm> {
+    if let Some(p) = algorithm.strip_prefix("genkt:") {
+        let p = p.parse().context("Invalid generalised kt power")?;
+        return Ok(JetAlgorithm::GenKt(p));
+    }
+    match algorithm {
+        "anti-kt" => Ok(JetAlgorithm::AntiKt),
+        "kt" => Ok(JetAlgorithm::Kt),
+        "ca" => Ok(JetAlgorithm::CambridgeAachen),
+        _ => bail!("Unknown jet algorithm {algorithm}\n{USAGE}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generalised_kt_power() {
+        assert_eq!(
+            parse_algorithm("genkt:0.5").unwrap(),
+            JetAlgorithm::GenKt(0.5)
+        );
+        assert!(parse_algorithm("genkt:").is_err());
+        assert!(parse_algorithm("genkt").is_err());
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Lines},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use particle_id::ParticleID;
+
+use crate::{
+    auto_decompress::auto_decompress,
+    event::{Event, Weight},
+    particle::{Particle, ParticleStatus},
+};
+
+/// Status code of outgoing particles
+const STATUS_OUTGOING: i32 = 1;
+/// Status code of incoming beam particles
+const STATUS_INCOMING: i32 = 4;
+/// Number of events parsed together in parallel
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_SIZE: usize = 256;
+
+fn open(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    auto_decompress(BufReader::new(file))
+}
+
+/// Check whether a (possibly compressed) file is in the HepMC3 ASCII format
+pub(crate) fn is_hepmc3(path: &Path) -> bool {
+    let Ok(file) = open(path) else {
+        return false;
+    };
+    file.lines()
+        .map_while(Result::ok)
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| is_header(&line))
+}
+
+/// Check whether buffered input starts with a HepMC3 header
+///
+/// Only looks at the data that is already buffered, so nothing is
+/// consumed from the input.
+pub(crate) fn has_header(input: &mut dyn BufRead) -> bool {
+    let Ok(buf) = input.fill_buf() else {
+        return false;
+    };
+    let start = buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(buf.len());
+    is_header(&String::from_utf8_lossy(&buf[start..]))
+}
+
+fn is_header(line: &str) -> bool {
+    line.starts_with("HepMC::Version 3") || line.starts_with("HepMC::Asciiv3")
+}
+
+/// Reader for events in the HepMC3 ASCII format
+///
+/// Only the information shown by evil is extracted, i.e. incoming and
+/// outgoing particles, weights, and coupling constants.
+pub(crate) struct Reader {
+    lines: Lines<Box<dyn BufRead + Send>>,
+    /// Start of the next event, if already read
+    next_event: Option<String>,
+}
+
+impl Reader {
+    pub(crate) fn new(path: &Path) -> Result<Self> {
+        Ok(Self::from_input(open(path)?))
+    }
+
+    /// Read from already opened and decompressed input
+    pub(crate) fn from_input(input: Box<dyn BufRead + Send>) -> Self {
+        Self {
+            lines: input.lines(),
+            next_event: None,
+        }
+    }
+
+    /// Lines of the next event, starting with the event line
+    fn next_record(&mut self) -> Option<Result<Vec<String>>> {
+        let header = match self.next_event.take() {
+            Some(header) => header,
+            None => loop {
+                match self.lines.next()? {
+                    Ok(line) if line.starts_with("E ") => break line,
+                    Ok(_) => {}
+                    Err(err) => return Some(Err(err.into())),
+                }
+            },
+        };
+        let mut record = vec![header];
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if line.starts_with("E ") {
+                self.next_event = Some(line);
+                break;
+            }
+            record.push(line);
+        }
+        Some(Ok(record))
+    }
+
+    /// All remaining events
+    ///
+    /// Events are parsed in parallel in chunks of `PARALLEL_CHUNK_SIZE`,
+    /// preserving their order.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn into_events(mut self) -> impl Iterator<Item = Result<Event>> {
+        use rayon::prelude::*;
+        std::iter::from_fn(move || {
+            let records = Vec::from_iter(
+                std::iter::from_fn(|| self.next_record())
+                    .take(PARALLEL_CHUNK_SIZE),
+            );
+            if records.is_empty() {
+                return None;
+            }
+            let events = records
+                .into_par_iter()
+                .map(|record| record.and_then(|lines| parse_event(&lines)))
+                .collect::<Vec<_>>();
+            Some(events)
+        })
+        .flatten()
+    }
+
+    /// All remaining events
+    #[cfg(not(feature = "parallel"))]
+    pub(crate) fn into_events(self) -> impl Iterator<Item = Result<Event>> {
+        self
+    }
+}
+
+impl Iterator for Reader {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.next_record()?;
+        Some(record.and_then(|lines| parse_event(&lines)))
+    }
+}
+
+/// Parse the lines of a single event, starting with the event line
+fn parse_event(lines: &[String]) -> Result<Event> {
+    let Some((header, lines)) = lines.split_first() else {
+        bail!("Empty event");
+    };
+    let mut event = Event::default();
+    event.info.id = header
+        .split_whitespace()
+        .nth(1)
+        .map(|id| id.parse())
+        .transpose()
+        .with_context(|| format!("Invalid event line: {header}"))?;
+    // momentum unit in GeV
+    let mut unit = 1.;
+    for line in lines {
+        let mut entries = line.split_whitespace();
+        match entries.next() {
+            Some("U") => {
+                unit = match entries.next() {
+                    Some("GEV") => 1.,
+                    Some("MEV") => 1e-3,
+                    _ => bail!("Invalid unit line: {line}"),
+                }
+            }
+            Some("W") => {
+                event.info.weights = entries
+                    .map(|w| w.parse::<f64>().map(Weight::from))
+                    .collect::<Result<_, _>>()
+                    .with_context(|| format!("Invalid weights: {line}"))?;
+            }
+            Some("A") => {
+                // event attributes have id 0
+                if entries.next() != Some("0") {
+                    continue;
+                }
+                let name = entries.next();
+                let value = entries.next().and_then(|v| v.parse().ok());
+                match name {
+                    Some("alphaQCD") => event.info.alpha_s = value,
+                    Some("alphaQED") => event.info.alpha = value,
+                    _ => {}
+                }
+            }
+            Some("P") => {
+                let (status, particle) = parse_particle(line, unit)
+                    .with_context(|| format!("Invalid particle: {line}"))?;
+                match status {
+                    STATUS_OUTGOING => event.out.push(particle),
+                    STATUS_INCOMING => event
+                        .incoming
+                        .push(particle.with_status(ParticleStatus::Incoming)),
+                    _ => event.intermediate.push(
+                        particle.with_status(ParticleStatus::Intermediate),
+                    ),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(event)
+}
+
+/// Parse a particle line, returning its status code and the particle
+///
+/// The format is `P id parent pdg_id px py pz e m status`
+fn parse_particle(line: &str, unit: f64) -> Result<(i32, Particle)> {
+    let entries = Vec::from_iter(line.split_whitespace());
+    let &[_, _, _, pdg_id, px, py, pz, e, _, status] = entries.as_slice()
+    else {
+        bail!("Expected 10 entries");
+    };
+    let status = status.parse()?;
+    let id = ParticleID::new(pdg_id.parse()?);
+    let [px, py, pz, e] = [px, py, pz, e].map(|p| p.parse::<f64>());
+    let p = [e?, px?, py?, pz?].map(|p| unit * p);
+    Ok((status, Particle::new(id, p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use event_file_reader::EventFileReader;
+
+    use super::*;
+
+    const SHOWERED: &str = "examples/showered.hepmc3.zst";
+
+    #[test]
+    fn detect_format() {
+        assert!(is_hepmc3(Path::new(SHOWERED)));
+        assert!(!is_hepmc3(Path::new("examples/showered.hepmc.zst")));
+    }
+
+    #[test]
+    fn read_showered() {
+        let events = Reader::new(Path::new(SHOWERED))
+            .unwrap()
+            .into_events()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(events.len(), 3);
+        // the same events in the HepMC2 format
+        let reference = EventFileReader::new("examples/showered.hepmc.zst")
+            .unwrap()
+            .take(events.len())
+            .map(|event| Event::from(event.unwrap()));
+        for (event, reference) in events.iter().zip(reference) {
+            assert_eq!(event.info.id, reference.info.id);
+            let values = |event: &Event| {
+                Vec::from_iter(event.info.weights.iter().map(|w| w.value))
+            };
+            assert_eq!(values(event), values(&reference));
+            assert_eq!(event.info.alpha_s, reference.info.alpha_s);
+            assert_eq!(event.info.alpha, reference.info.alpha);
+            assert_eq!(event.incoming.len(), 2);
+            assert_eq!(event.incoming, reference.incoming);
+            assert_eq!(event.out, reference.out);
+            assert_eq!(event.intermediate.len(), reference.intermediate.len());
+        }
+    }
+
+    #[test]
+    fn momentum_unit() {
+        let event = "\
+HepMC::Version 3.02.05
+HepMC::Asciiv3-START_EVENT_LISTING
+E 7 1 3
+U MEV MM
+P 1 0 2212 0 0 6500000 6500000 938.272 4
+V -1 0 [1]
+P 2 -1 11 3000 0 4000 5000 0 1
+P 3 -1 23 0 0 0 91188 91188 2
+HepMC::Asciiv3-END_EVENT_LISTING
+";
+        let mut events =
+            Reader::from_input(Box::new(Cursor::new(event))).into_events();
+        let event = events.next().unwrap().unwrap();
+        assert!(events.next().is_none());
+        assert_eq!(event.info.id, Some(7));
+        assert_eq!(event.incoming.len(), 1);
+        assert_eq!(event.incoming[0].p, [6500., 0., 0., 6500.]);
+        assert_eq!(event.out.len(), 1);
+        assert_eq!(event.out[0].p, [5., 3., 0., 4.]);
+        assert_eq!(event.intermediate.len(), 1);
+        assert_eq!(event.intermediate[0].p, [91.188, 0., 0., 0.]);
+    }
+}
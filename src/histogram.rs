@@ -0,0 +1,143 @@
+use std::collections::BTreeSet;
+
+use particle_id::ParticleID;
+use serde::{Deserialize, Serialize};
+
+use crate::event::Event;
+
+/// Number and range of equally sized bins
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub(crate) struct Binning {
+    pub(crate) nbins: usize,
+    pub(crate) min: f64,
+    pub(crate) max: f64,
+}
+
+impl Binning {
+    pub(crate) fn width(&self) -> f64 {
+        (self.max - self.min) / self.nbins as f64
+    }
+
+    /// Index of the bin containing `x`, if any
+    fn bin(&self, x: f64) -> Option<usize> {
+        if !(self.min..self.max).contains(&x) {
+            return None;
+        }
+        let bin = ((x - self.min) / self.width()) as usize;
+        Some(bin.min(self.nbins - 1))
+    }
+}
+
+/// Weighted histogram
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Histogram {
+    binning: Binning,
+    bins: Vec<f64>,
+    sum_weights: f64,
+    sum_weights_sq: f64,
+}
+
+impl Histogram {
+    pub(crate) fn new(binning: Binning) -> Self {
+        Self {
+            binning,
+            bins: vec![0.; binning.nbins],
+            sum_weights: 0.,
+            sum_weights_sq: 0.,
+        }
+    }
+
+    /// Add `weight` to the bin containing `x`, ignoring values out of range
+    pub(crate) fn fill(&mut self, x: f64, weight: f64) {
+        if let Some(bin) = self.binning.bin(x) {
+            self.bins[bin] += weight;
+            self.sum_weights += weight;
+            self.sum_weights_sq += weight * weight;
+        }
+    }
+
+    pub(crate) fn binning(&self) -> Binning {
+        self.binning
+    }
+
+    /// Sum of the weights of all entries inside the binning range
+    pub(crate) fn sum_weights(&self) -> f64 {
+        self.sum_weights
+    }
+
+    /// Sum of the squared weights of all entries inside the binning range
+    pub(crate) fn sum_weights_sq(&self) -> f64 {
+        self.sum_weights_sq
+    }
+
+    /// Effective number of entries (Σw)²/Σw²
+    ///
+    /// For unit weights this is the number of entries. Large cancellations
+    /// between positive and negative weights lead to small values.
+    pub(crate) fn effective_entries(&self) -> f64 {
+        if self.sum_weights_sq > 0. {
+            self.sum_weights.powi(2) / self.sum_weights_sq
+        } else {
+            0.
+        }
+    }
+
+    /// Bin centres together with the bin contents
+    pub(crate) fn bins(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        let Binning { min, .. } = self.binning;
+        let width = self.binning.width();
+        self.bins
+            .iter()
+            .enumerate()
+            .map(move |(n, &content)| (min + (n as f64 + 0.5) * width, content))
+    }
+
+    /// Corners of the histogram outline, for drawing it as a line
+    pub(crate) fn outline(&self) -> Vec<[f64; 2]> {
+        let Binning { min, .. } = self.binning;
+        let width = self.binning.width();
+        let mut points = vec![[min, 0.]];
+        for (n, &content) in self.bins.iter().enumerate() {
+            let start = min + n as f64 * width;
+            points.push([start, content]);
+            points.push([start + width, content]);
+        }
+        points.push([min + self.bins.len() as f64 * width, 0.]);
+        points
+    }
+}
+
+/// Weight for filling histograms
+///
+/// This is one for unweighted events or if `weighted` is not set.
+pub(crate) fn event_weight(event: &Event, weighted: bool) -> f64 {
+    if weighted {
+        event.info.weight().unwrap_or(1.)
+    } else {
+        1.
+    }
+}
+
+/// Species of outgoing particles in a sample, ordered by PDG id
+///
+/// The list is only recomputed when the number of events changes.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SpeciesList {
+    nevents: usize,
+    species: Vec<ParticleID>,
+}
+
+impl SpeciesList {
+    pub(crate) fn get(&mut self, events: &[Event]) -> &[ParticleID] {
+        if self.nevents != events.len() {
+            let ids = BTreeSet::from_iter(
+                events
+                    .iter()
+                    .flat_map(|ev| ev.out.iter().map(|p| p.id.id())),
+            );
+            self.species = Vec::from_iter(ids.into_iter().map(ParticleID::new));
+            self.nevents = events.len();
+        }
+        &self.species
+    }
+}
@@ -1,12 +1,33 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+mod archive;
+mod auto_decompress;
 mod clustering;
 mod event;
 mod export;
+mod headless;
+mod hepmc3;
+mod histogram;
+mod observables;
 mod particle;
 mod plotter;
+mod preset;
+mod stream;
 mod windows;
 
 pub use app::TemplateApp;
-pub use event::Event;
+pub use clustering::{
+    cluster, cluster_sequence, cluster_with_constituents, exclusive_subjets,
+    jet_area_radius, n_subjettiness, ClusterSequence, ClusterState,
+    ClusterSummary, Filtering, Grooming, JetAlgorithm, JetDefinition, SoftDrop,
+    Trimming,
+};
+pub use event::{Event, EventInfo, Weight};
+pub use export::render_svg;
+pub use headless::HeadlessExport;
+pub use jetty::PseudoJet;
+pub use observables::{sphericity, thrust, Observable};
+pub use particle::{Particle, ParticleGroup, ParticleStatus, SpinType};
+pub use plotter::{PlotKind, Settings};
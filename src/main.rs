@@ -6,9 +6,29 @@
 fn main() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    match evil::HeadlessExport::from_args(std::env::args().skip(1)) {
+        Ok(Some(export)) => {
+            if let Err(err) = export.run() {
+                eprintln!("{err:#}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{err:#}");
+            std::process::exit(1);
+        }
+    }
+
+    let options = eframe::NativeOptions {
+        // the window size is restored from the app state instead
+        persist_window: false,
+        ..Default::default()
+    };
     eframe::run_native(
         "evil",
-        eframe::NativeOptions::default(),
+        options,
         Box::new(|cc| Box::new(evil::TemplateApp::new(cc))),
     )
 }
@@ -0,0 +1,227 @@
+use jetty::PseudoJet;
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::event::Event;
+
+/// Number of hardest particles combined into starting axes for thrust
+const THRUST_SEEDS: usize = 4;
+const MAX_THRUST_ITERATIONS: usize = 32;
+
+/// Per-event scalar observables
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Debug,
+    Deserialize,
+    Serialize,
+)]
+pub enum Observable {
+    /// Scalar sum of jet transverse momenta
+    #[default]
+    #[strum(to_string = "HT")]
+    Ht,
+    /// Missing transverse momentum, see [Event::missing_pt]
+    #[strum(to_string = "MET")]
+    Met,
+    #[strum(to_string = "leading jet pT")]
+    LeadingJetPt,
+    #[strum(to_string = "number of jets")]
+    NJets,
+    /// Invariant mass of the two hardest jets
+    #[strum(to_string = "m_jj")]
+    Mjj,
+    #[strum(to_string = "thrust")]
+    Thrust,
+    #[strum(to_string = "sphericity")]
+    Sphericity,
+}
+
+impl Observable {
+    pub fn eval(&self, event: &Event, jets: &[PseudoJet]) -> f64 {
+        use Observable::*;
+        match self {
+            Ht => jets.iter().map(|j| f64::from(j.pt())).sum(),
+            Met => {
+                let [px, py] = event.missing_pt();
+                (px * px + py * py).sqrt()
+            }
+            LeadingJetPt => jets
+                .iter()
+                .map(|j| f64::from(j.pt()))
+                .max_by(|a, b| a.total_cmp(b))
+                .unwrap_or_default(),
+            NJets => jets.len() as f64,
+            Mjj => {
+                let mut jets = Vec::from_iter(jets.iter());
+                if jets.len() < 2 {
+                    return 0.;
+                }
+                jets.sort_unstable_by_key(|jet| std::cmp::Reverse(jet.pt2()));
+                let [e, px, py, pz] = [
+                    jets[0].e() + jets[1].e(),
+                    jets[0].px() + jets[1].px(),
+                    jets[0].py() + jets[1].py(),
+                    jets[0].pz() + jets[1].pz(),
+                ]
+                .map(f64::from);
+                (e * e - px * px - py * py - pz * pz).max(0.).sqrt()
+            }
+            Thrust => thrust(event),
+            Sphericity => sphericity(event),
+        }
+    }
+}
+
+/// Three-momenta of all visible outgoing particles
+fn visible_momenta(event: &Event) -> Vec<Vector3<f64>> {
+    Vec::from_iter(
+        event
+            .out
+            .iter()
+            .filter(|p| !p.is_neutrino())
+            .map(|p| Vector3::new(p.p[1], p.p[2], p.p[3])),
+    )
+}
+
+/// Thrust T = max Σ|p·n| / Σ|p| of the visible outgoing particles
+///
+/// The thrust axis n is found iteratively, starting from all sign
+/// combinations of the momenta of the hardest particles. For each start,
+/// the axis is replaced by the sum of the momenta in its hemisphere minus
+/// the ones in the opposite hemisphere until the hemispheres no longer
+/// change. Returns zero for events without visible momentum.
+pub fn thrust(event: &Event) -> f64 {
+    let momenta = visible_momenta(event);
+    let norm: f64 = momenta.iter().map(|p| p.norm()).sum();
+    if norm == 0. {
+        return 0.;
+    }
+    let mut seeds = momenta.clone();
+    seeds
+        .sort_unstable_by(|a, b| b.norm_squared().total_cmp(&a.norm_squared()));
+    seeds.truncate(THRUST_SEEDS);
+    // the overall sign of the axis doesn't matter
+    let nstarts = 1 << (seeds.len() - 1);
+    let mut max = 0.;
+    for signs in 0..nstarts {
+        let mut axis =
+            seeds
+                .iter()
+                .enumerate()
+                .fold(Vector3::zeros(), |axis, (n, p)| {
+                    if signs & (1 << n) == 0 {
+                        axis + p
+                    } else {
+                        axis - p
+                    }
+                });
+        for _ in 0..MAX_THRUST_ITERATIONS {
+            let next = momenta.iter().fold(Vector3::zeros(), |next, p| {
+                if p.dot(&axis) >= 0. {
+                    next + p
+                } else {
+                    next - p
+                }
+            });
+            if next == axis {
+                break;
+            }
+            axis = next;
+        }
+        let Some(axis) = axis.try_normalize(0.) else {
+            continue;
+        };
+        let projection: f64 = momenta.iter().map(|p| p.dot(&axis).abs()).sum();
+        max = f64::max(max, projection);
+    }
+    max / norm
+}
+
+/// Sphericity S = 3/2 (λ₂ + λ₃) of the visible outgoing particles
+///
+/// λ₂ and λ₃ are the two smallest eigenvalues of the normalised momentum
+/// tensor Σ pᵃpᵇ / Σ|p|². Returns zero for events without visible momentum.
+pub fn sphericity(event: &Event) -> f64 {
+    let momenta = visible_momenta(event);
+    let norm: f64 = momenta.iter().map(|p| p.norm_squared()).sum();
+    if norm == 0. {
+        return 0.;
+    }
+    let tensor = momenta
+        .iter()
+        .fold(Matrix3::zeros(), |tensor, p| tensor + p * p.transpose())
+        / norm;
+    let mut eigenvalues = tensor.symmetric_eigenvalues();
+    eigenvalues.as_mut_slice().sort_unstable_by(f64::total_cmp);
+    1.5 * (eigenvalues[0] + eigenvalues[1])
+}
+
+/// Kinds of conditions for finding events
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Eq,
+    PartialEq,
+    Debug,
+    Deserialize,
+    Serialize,
+)]
+pub enum EventCondition {
+    #[default]
+    #[strum(to_string = "at least N particles")]
+    MinMultiplicity,
+    #[strum(to_string = "a particle with pT above")]
+    ParticleAbovePt,
+}
+
+/// Condition for finding events
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct EventPredicate {
+    pub condition: EventCondition,
+    pub multiplicity: usize,
+    pub pt: f64,
+}
+
+impl Default for EventPredicate {
+    fn default() -> Self {
+        Self {
+            condition: EventCondition::default(),
+            multiplicity: 10,
+            pt: 100.,
+        }
+    }
+}
+
+impl EventPredicate {
+    pub fn matches(&self, event: &Event) -> bool {
+        match self.condition {
+            EventCondition::MinMultiplicity => {
+                event.out.len() >= self.multiplicity
+            }
+            EventCondition::ParticleAbovePt => {
+                event.out.iter().any(|p| p.pt > self.pt)
+            }
+        }
+    }
+
+    /// Index of the next matching event after `start`, wrapping around
+    pub fn find_next(&self, events: &[Event], start: usize) -> Option<usize> {
+        let nevents = events.len();
+        (1..=nevents)
+            .map(|n| (start + n) % nevents)
+            .find(|&idx| self.matches(&events[idx]))
+    }
+}
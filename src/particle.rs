@@ -1,16 +1,32 @@
+use std::borrow::Cow;
+
 use particle_id::sm_elementary_particles::gluon;
 use particle_id::ParticleID;
+use serde::Serialize;
+use strum::{Display, EnumIter};
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Serialize)]
 pub struct Particle {
     pub id: ParticleID,
     pub p: [f64; 4],
     pub y: f64,
     pub phi: f64,
     pub pt: f64,
+    pub status: ParticleStatus,
+}
+
+/// Role of a particle in the event record
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Debug, Serialize)]
+pub enum ParticleStatus {
+    Incoming,
+    Outgoing,
+    /// Decayed resonances, hard-process partons, and other particles
+    /// that are neither incoming nor outgoing
+    Intermediate,
 }
 
 impl Particle {
+    /// Outgoing particle with the given id and four-momentum
     pub fn new(id: ParticleID, p: [f64; 4]) -> Self {
         Particle {
             id,
@@ -18,9 +34,14 @@ impl Particle {
             y: y(&p),
             phi: phi(&p),
             pt: pt(&p),
+            status: ParticleStatus::Outgoing,
         }
     }
 
+    pub fn with_status(self, status: ParticleStatus) -> Self {
+        Self { status, ..self }
+    }
+
     pub fn spin_type(&self) -> SpinType {
         spin_type(self.id)
     }
@@ -29,17 +50,84 @@ impl Particle {
         self.id.is_anti_particle()
     }
 
-    pub fn name(&self) -> &'static str {
+    pub fn name(&self) -> Cow<'static, str> {
         particle_name(self.id)
     }
 
     pub fn is_parton(&self) -> bool {
         self.id == gluon || self.id.id().abs() <= 5
     }
+
+    pub fn is_neutrino(&self) -> bool {
+        matches!(self.id.id().abs(), 12 | 14 | 16)
+    }
+
+    pub fn is_nucleus(&self) -> bool {
+        is_nucleus(self.id)
+    }
+
+    /// Electric charge in units of the elementary charge
+    pub fn charge(&self) -> f64 {
+        three_charge(self.id) as f64 / 3.
+    }
+
+    /// Pseudorapidity -ln tan(θ/2)
+    pub fn eta(&self) -> f64 {
+        (self.p[3] / self.pt).asinh()
+    }
+
+    pub fn energy(&self) -> f64 {
+        self.p[0]
+    }
+
+    /// Invariant mass, zero for slightly negative squared masses
+    pub fn mass(&self) -> f64 {
+        let [e, px, py, pz] = self.p;
+        (e * e - px * px - py * py - pz * pz).max(0.).sqrt()
+    }
+
+    /// Transverse energy E sin(θ)
+    pub fn et(&self) -> f64 {
+        let p = self.pt.hypot(self.p[3]);
+        if p > 0. {
+            self.p[0] * self.pt / p
+        } else {
+            0.
+        }
+    }
+
+    pub fn delta_r(&self, other: &Particle) -> f64 {
+        let dy = self.y - other.y;
+        let dphi = delta_phi(self.phi, other.phi);
+        (dy * dy + dphi * dphi).sqrt()
+    }
+}
+
+/// Invariant mass of a pair of particles
+pub fn invariant_mass(a: &Particle, b: &Particle) -> f64 {
+    let [e, px, py, pz] = [0, 1, 2, 3].map(|i| a.p[i] + b.p[i]);
+    let m2 = e * e - px * px - py * py - pz * pz;
+    m2.max(0.).sqrt()
 }
 
-pub fn particle_name(id: ParticleID) -> &'static str {
-    match id.id() {
+/// Difference in azimuthal angle, mapped to [-π, π]
+pub fn delta_phi(phi1: f64, phi2: f64) -> f64 {
+    use std::f64::consts::PI;
+    let dphi = (phi1 - phi2).rem_euclid(2. * PI);
+    if dphi > PI {
+        dphi - 2. * PI
+    } else {
+        dphi
+    }
+}
+
+/// Short name of a particle
+///
+/// Common Standard Model particles get hand-picked Unicode symbols, other
+/// particles use the symbol or name from the PDG tables. Unknown particles
+/// are shown by their id.
+pub fn particle_name(id: ParticleID) -> Cow<'static, str> {
+    let name = match id.id() {
         1 => "d",
         2 => "u",
         3 => "s",
@@ -70,8 +158,64 @@ pub fn particle_name(id: ParticleID) -> &'static str {
         -15 => "τ⁺",
         -16 => " ̅ν(τ)",
         -24 => "W¯",
-        _ => "N/A",
-    }
+        _ if is_nucleus(id) => {
+            let [z, a] = nucleus_charge_and_mass_number(id);
+            return Cow::Owned(format!("nucleus (Z = {z}, A = {a})"));
+        }
+        _ => {
+            return match id.symbol().or(id.name()) {
+                Some(name) => Cow::Borrowed(name),
+                None => Cow::Owned(id.id().to_string()),
+            }
+        }
+    };
+    Cow::Borrowed(name)
+}
+
+/// Check for nuclear PDG codes of the form ±10LZZZAAAI
+pub fn is_nucleus(id: ParticleID) -> bool {
+    (1_000_000_000..2_000_000_000).contains(&id.id().abs())
+}
+
+/// Charge and mass number of a nucleus
+fn nucleus_charge_and_mass_number(id: ParticleID) -> [i32; 2] {
+    let id = id.id().abs();
+    [(id / 10_000) % 1000, (id / 10) % 1000]
+}
+
+/// Electric charge in units of a third of the elementary charge
+///
+/// Hadron charges are derived from the quark content encoded in the PDG
+/// code. Unknown particles are treated as neutral.
+pub fn three_charge(id: ParticleID) -> i32 {
+    // charges of d, u, s, c, b, t
+    const QUARK_CHARGES: [i32; 6] = [-1, 2, -1, 2, -1, 2];
+    let quark_charge = |q: i32| match q {
+        1..=6 => QUARK_CHARGES[q as usize - 1],
+        _ => 0,
+    };
+    let abs_id = id.id().abs();
+    let charge = match abs_id {
+        1..=6 => quark_charge(abs_id),
+        11 | 13 | 15 | 17 => -3,
+        24 | 34 | 37 => 3,
+        _ if abs_id <= 100 => 0,
+        _ if is_nucleus(id) => 3 * nucleus_charge_and_mass_number(id)[0],
+        _ => {
+            let [q1, q2, q3] = [1000, 100, 10].map(|d| (abs_id / d) % 10);
+            if q1 != 0 {
+                // baryon or diquark
+                quark_charge(q1) + quark_charge(q2) + quark_charge(q3)
+            } else if q2 % 2 == 0 {
+                // meson, the heavier quark is up-type and not an antiquark
+                quark_charge(q2) - quark_charge(q3)
+            } else {
+                // meson, the heavier quark is down-type and an antiquark
+                quark_charge(q3) - quark_charge(q2)
+            }
+        }
+    };
+    id.id().signum() * charge
 }
 
 pub fn spin_type(id: ParticleID) -> SpinType {
@@ -83,6 +227,45 @@ pub fn spin_type(id: ParticleID) -> SpinType {
     }
 }
 
+/// Groups of elementary particles that are styled together
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, EnumIter)]
+pub enum ParticleGroup {
+    #[strum(to_string = "all quarks")]
+    Quarks,
+    #[strum(to_string = "all charged leptons")]
+    Leptons,
+    #[strum(to_string = "all neutrinos")]
+    Neutrinos,
+    #[strum(to_string = "all bosons")]
+    Bosons,
+}
+
+impl ParticleGroup {
+    pub fn contains(&self, id: ParticleID) -> bool {
+        let abs_id = id.id().abs();
+        match self {
+            Self::Quarks => spin_type(id) == SpinType::Fermion && abs_id <= 6,
+            Self::Leptons => matches!(abs_id, 11 | 13 | 15),
+            Self::Neutrinos => matches!(abs_id, 12 | 14 | 16),
+            // the W is the only boson with a distinct antiparticle
+            Self::Bosons => {
+                spin_type(id) == SpinType::Boson
+                    && (id.id() > 0 || abs_id == 24)
+            }
+        }
+    }
+
+    /// Particles and antiparticles in the group
+    pub fn members(&self) -> Vec<ParticleID> {
+        Vec::from_iter(
+            (1..=25)
+                .flat_map(|id| [id, -id])
+                .map(ParticleID::new)
+                .filter(|&id| self.contains(id)),
+        )
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum SpinType {
     Boson,
@@ -105,3 +288,41 @@ fn pt2(p: &[f64; 4]) -> f64 {
 fn pt(p: &[f64; 4]) -> f64 {
     pt2(p).sqrt()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f64 = 1e-12;
+
+    #[test]
+    fn massless_kinematics() {
+        let p = Particle::new(ParticleID::new(22), [5., 3., 0., 4.]);
+        assert!((p.eta() - 3_f64.ln()).abs() < EPS);
+        assert!(p.mass().abs() < EPS);
+        assert!((p.et() - 3.).abs() < EPS);
+    }
+
+    #[test]
+    fn massive_kinematics() {
+        let p = Particle::new(ParticleID::new(23), [13., 3., 4., 0.]);
+        assert!(p.eta().abs() < EPS);
+        assert!((p.mass() - 12.).abs() < EPS);
+        assert!((p.et() - 13.).abs() < EPS);
+    }
+
+    #[test]
+    fn negative_squared_mass() {
+        let p = Particle::new(ParticleID::new(22), [5., 3., 0., 4. + 1e-9]);
+        assert_eq!(p.mass(), 0.);
+    }
+
+    #[test]
+    fn lead_nucleus() {
+        let pb208 = ParticleID::new(1000822080);
+        assert!(is_nucleus(pb208));
+        assert_eq!(particle_name(pb208), "nucleus (Z = 82, A = 208)");
+        assert_eq!(three_charge(pb208), 3 * 82);
+        assert!(!is_nucleus(ParticleID::new(2212)));
+    }
+}
@@ -1,14 +1,19 @@
 use crate::event::Event;
-use crate::particle::{spin_type, Particle, SpinType};
+use crate::particle::{
+    delta_phi, is_nucleus, spin_type, Particle, ParticleStatus, SpinType,
+};
 
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::f64::consts::PI;
-use std::ops::RangeInclusive;
+use std::ops::{Range, RangeInclusive};
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use egui::{Stroke, Ui};
-use egui_plot::{Legend, Plot, PlotPoints, Points, Polygon};
+use egui_plot::{
+    Legend, Line, Plot, PlotBounds, PlotPoint, PlotPoints, Points, Polygon,
+    Text, VLine,
+};
 use jetty::PseudoJet;
 use log::debug;
 use nalgebra::{Rotation3, Point3};
@@ -20,27 +25,58 @@ use serde::{Deserialize, Serialize};
 use strum::{Display, EnumIter};
 
 const PHI_SCALE: f64 = PI / 2.;
-const PHI_AXIS_MIN: f64 = -2.2;
-const PHI_AXIS_MAX: f64 = -PHI_AXIS_MIN;
+const PHI_AXIS_MAX: f64 = 2.2;
+/// Marker size factor for the constituents of the jet under the pointer
+const HOVER_ENLARGEMENT: f32 = 1.5;
+const MISSING_PT: &str = "missing pT";
+const INCOMING: &str = "incoming";
+
+/// Line through points in the 3D plot
+type Line3 = Vec<(f64, f64, f64)>;
 
 #[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct ParticleStyle {
     pub colour: egui::Color32,
     pub shape: MarkerShape,
     pub size: f32,
+    #[serde(default = "default_outline")]
+    pub outline: egui::Color32,
+    /// Width of the marker outline, zero for no outline
+    #[serde(default = "default_outline_width")]
+    pub outline_width: f32,
 }
 
 impl ParticleStyle {
-    pub fn default_for(p: ParticleID) -> Self {
+    pub fn default_for(p: ParticleID, palette: Palette) -> Self {
         const DEFAULT_MARKER_SIZE: f32 = 3.;
+        const NUCLEUS_MARKER_SIZE: f32 = 6.;
+        if is_nucleus(p) {
+            return Self {
+                colour: default_colour_for(p, palette),
+                shape: MarkerShape::Circle,
+                size: NUCLEUS_MARKER_SIZE,
+                outline: default_outline(),
+                outline_width: default_outline_width(),
+            };
+        }
         Self {
-            colour: default_colour_for(p),
+            colour: default_colour_for(p, palette),
             shape: default_shape_for(p),
             size: DEFAULT_MARKER_SIZE,
+            outline: default_outline(),
+            outline_width: default_outline_width(),
         }
     }
 }
 
+fn default_outline() -> egui::Color32 {
+    egui::Color32::BLACK
+}
+
+fn default_outline_width() -> f32 {
+    0.5
+}
+
 fn default_shape_for(p: ParticleID) -> MarkerShape {
     use MarkerShape::*;
     match spin_type(p) {
@@ -56,7 +92,36 @@ fn default_shape_for(p: ParticleID) -> MarkerShape {
     }
 }
 
-fn default_colour_for(p: ParticleID) -> egui::Color32 {
+/// Set of default particle colours
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum Palette {
+    #[default]
+    #[strum(to_string = "default")]
+    Default,
+    /// Okabe-Ito colours, distinguishable with colour vision deficiencies
+    #[strum(to_string = "colour-blind safe")]
+    ColourBlind,
+}
+
+fn default_colour_for(p: ParticleID, palette: Palette) -> egui::Color32 {
+    match palette {
+        Palette::Default => standard_colour_for(p),
+        Palette::ColourBlind => colour_blind_colour_for(p),
+    }
+}
+
+fn standard_colour_for(p: ParticleID) -> egui::Color32 {
     const CYAN: egui::Color32 = egui::Color32::from_rgb(0, 159, 223);
     const ORANGE: egui::Color32 = egui::Color32::from_rgb(241, 143, 31);
     const MAGENTA: egui::Color32 = egui::Color32::from_rgb(255, 0, 255);
@@ -89,6 +154,41 @@ fn default_colour_for(p: ParticleID) -> egui::Color32 {
     }
 }
 
+fn colour_blind_colour_for(p: ParticleID) -> egui::Color32 {
+    const ORANGE: egui::Color32 = egui::Color32::from_rgb(230, 159, 0);
+    const SKY_BLUE: egui::Color32 = egui::Color32::from_rgb(86, 180, 233);
+    const BLUISH_GREEN: egui::Color32 = egui::Color32::from_rgb(0, 158, 115);
+    const YELLOW: egui::Color32 = egui::Color32::from_rgb(240, 228, 66);
+    const BLUE: egui::Color32 = egui::Color32::from_rgb(0, 114, 178);
+    const VERMILLION: egui::Color32 = egui::Color32::from_rgb(213, 94, 0);
+    const REDDISH_PURPLE: egui::Color32 =
+        egui::Color32::from_rgb(204, 121, 167);
+    const GREY: egui::Color32 = egui::Color32::from_rgb(160, 160, 160);
+
+    const DEFAULT_COLOR: egui::Color32 = egui::Color32::GRAY;
+    use particle_id::sm_elementary_particles as sm;
+    match p {
+        sm::down => SKY_BLUE,
+        sm::up => REDDISH_PURPLE,
+        sm::strange => BLUE,
+        sm::charm => VERMILLION,
+        sm::bottom => BLUISH_GREEN,
+        sm::top => ORANGE,
+        sm::electron => YELLOW,
+        sm::electron_neutrino => GREY,
+        sm::muon => ORANGE,
+        sm::muon_neutrino => GREY,
+        sm::tau => VERMILLION,
+        sm::tau_neutrino => GREY,
+        sm::gluon => BLUE,
+        sm::photon => YELLOW,
+        sm::Z => VERMILLION,
+        sm::W_plus => BLUISH_GREEN,
+        sm::Higgs => REDDISH_PURPLE,
+        _ => DEFAULT_COLOR,
+    }
+}
+
 // egui MarkerShape doesn't derive Deserialize/Serialize
 #[derive(
     Deserialize,
@@ -153,37 +253,352 @@ impl From<egui_plot::MarkerShape> for MarkerShape {
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Settings {
-    // pub frame: egui::Color32,
-    // pub background: egui::Color32,
+    /// Colour of the plot frame
+    #[serde(default = "default_frame")]
+    pub frame: egui::Color32,
+    /// Plot background, transparent keeps the background of the theme
+    #[serde(default)]
+    pub background: egui::Color32,
     pub particles: HashMap<ParticleID, ParticleStyle>,
     pub jets: egui::Color32,
+    /// Particles with a smaller transverse momentum are not shown
+    #[serde(default)]
+    pub min_pt: HashMap<ParticleID, f64>,
+    #[serde(default)]
+    pub pt_colours: PtColours,
+    #[serde(default)]
+    pub marker_size: MarkerSize,
+    #[serde(default)]
+    pub display_cuts: DisplayCuts,
+    /// Colours of particles without a custom style
+    #[serde(default)]
+    pub palette: Palette,
+    /// Draw each jet in a different colour instead of `jets`
+    #[serde(default)]
+    pub per_jet_colours: bool,
+    /// Which particles to show, in addition to any cuts
+    #[serde(default)]
+    pub status_filter: StatusFilter,
+    /// Longitudinal coordinate on the horizontal axes
+    #[serde(default)]
+    pub rapidity: Rapidity,
 }
 impl Settings {
+    pub fn is_visible(&self, particle: &Particle) -> bool {
+        let passes_species_cut = match self.min_pt.get(&particle.id) {
+            Some(min_pt) => particle.pt >= *min_pt,
+            None => true,
+        };
+        passes_species_cut && self.display_cuts.passes(particle)
+    }
+
+    /// Particles of an event selected by the status filter and all cuts
+    pub fn visible_particles(&self, event: &Event) -> Vec<Particle> {
+        let (out, intermediate): (&[Particle], &[Particle]) =
+            match self.status_filter {
+                StatusFilter::Outgoing => (&event.out, &[]),
+                StatusFilter::Intermediate => (&[], &event.intermediate),
+                StatusFilter::All => (&event.out, &event.intermediate),
+            };
+        out.iter()
+            .chain(intermediate)
+            .filter(|p| self.is_visible(p))
+            .copied()
+            .collect()
+    }
+
     pub fn get_particle_style(&mut self, pid: ParticleID) -> ParticleStyle {
         *self.get_particle_style_mut(pid)
     }
 
+    /// Style for a particle, without inserting missing defaults
+    pub fn particle_style(&self, pid: ParticleID) -> ParticleStyle {
+        self.particles
+            .get(&pid)
+            .copied()
+            .unwrap_or_else(|| ParticleStyle::default_for(pid, self.palette))
+    }
+
     pub fn get_particle_style_mut(
         &mut self,
         pid: ParticleID,
     ) -> &mut ParticleStyle {
         self.particles
             .entry(pid)
-            .or_insert_with(|| ParticleStyle::default_for(pid))
+            .or_insert_with(|| ParticleStyle::default_for(pid, self.palette))
+    }
+
+    /// Fill colour of the jet with the given index
+    ///
+    /// With per-jet colours, the colours are cycled from a fixed palette
+    /// with the opacity of the common jet colour.
+    pub fn jet_colour(&self, idx: usize) -> egui::Color32 {
+        use plotters::style::{Color, Palette, Palette99};
+        if !self.per_jet_colours {
+            return self.jets;
+        }
+        let (r, g, b) = Palette99::pick(idx).rgb();
+        egui::Color32::from_rgba_unmultiplied(r, g, b, self.jets.a())
+    }
+
+    /// Switch to a different palette, keeping customised styles
+    ///
+    /// Styles that only differ from the defaults of the old palette are
+    /// removed, so they are replaced by the new defaults.
+    pub fn set_palette(&mut self, palette: Palette) {
+        let old = self.palette;
+        self.particles.retain(|&pid, style| {
+            *style != ParticleStyle::default_for(pid, old)
+        });
+        self.palette = palette;
+    }
+
+    /// Use the frame and background colours for plots drawn in `ui`
+    fn apply_plot_colours(&self, ui: &mut Ui) {
+        let visuals = ui.visuals_mut();
+        if self.background != egui::Color32::TRANSPARENT {
+            visuals.extreme_bg_color = self.background;
+        }
+        visuals.widgets.noninteractive.bg_stroke.color = self.frame;
     }
 }
 
+fn default_frame() -> egui::Color32 {
+    egui::Color32::GRAY
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
-            // frame: egui::Color32::GRAY,
-            // background: egui::Color32::TRANSPARENT,
+            frame: default_frame(),
+            background: egui::Color32::TRANSPARENT,
             particles: HashMap::default(),
             jets: egui::Color32::from_rgba_premultiplied(100, 100, 100, 80),
+            min_pt: HashMap::default(),
+            pt_colours: PtColours::default(),
+            marker_size: MarkerSize::default(),
+            display_cuts: DisplayCuts::default(),
+            palette: Palette::default(),
+            per_jet_colours: false,
+            status_filter: StatusFilter::default(),
+            rapidity: Rapidity::default(),
         }
     }
 }
 
+/// Selection of shown particles by their status
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum StatusFilter {
+    #[default]
+    #[strum(to_string = "outgoing")]
+    Outgoing,
+    #[strum(to_string = "intermediate")]
+    Intermediate,
+    #[strum(to_string = "outgoing and intermediate")]
+    All,
+}
+
+/// Cuts on the shown particles for all species
+///
+/// These only affect what is drawn and exported, not jet clustering.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct DisplayCuts {
+    pub min_pt: f64,
+    pub max_abs_y: Option<f64>,
+}
+
+impl DisplayCuts {
+    pub fn passes(&self, particle: &Particle) -> bool {
+        particle.pt >= self.min_pt
+            && self.max_abs_y.is_none_or(|max| particle.y.abs() <= max)
+    }
+}
+
+/// What determines the size of particle markers
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum MarkerSizing {
+    /// Use the size from the particle style
+    #[default]
+    #[strum(to_string = "constant")]
+    Constant,
+    #[strum(to_string = "energy")]
+    Energy,
+    #[strum(to_string = "transverse momentum")]
+    Pt,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct MarkerSize {
+    pub sizing: MarkerSizing,
+    /// Marker size per GeV of energy or transverse momentum
+    pub scale: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for MarkerSize {
+    fn default() -> Self {
+        Self {
+            sizing: MarkerSizing::default(),
+            scale: 0.05,
+            min: 2.,
+            max: 20.,
+        }
+    }
+}
+
+impl MarkerSize {
+    /// Size of the marker for a particle with the given style size
+    pub fn of(&self, particle: &Particle, style_size: f32) -> f32 {
+        let value = match self.sizing {
+            MarkerSizing::Constant => return style_size,
+            MarkerSizing::Energy => particle.p[0],
+            MarkerSizing::Pt => particle.pt,
+        };
+        (self.scale * value as f32).max(self.min).min(self.max)
+    }
+}
+
+/// Colour maps for colouring particles by transverse momentum
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum ColourMap {
+    #[default]
+    #[strum(to_string = "viridis")]
+    Viridis,
+    #[strum(to_string = "inferno")]
+    Inferno,
+}
+
+impl ColourMap {
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            ColourMap::Viridis => &[
+                [0x44, 0x01, 0x54],
+                [0x3b, 0x52, 0x8b],
+                [0x21, 0x91, 0x8c],
+                [0x5e, 0xc9, 0x62],
+                [0xfd, 0xe7, 0x25],
+            ],
+            ColourMap::Inferno => &[
+                [0x00, 0x00, 0x04],
+                [0x42, 0x0a, 0x68],
+                [0x93, 0x26, 0x67],
+                [0xdd, 0x51, 0x3a],
+                [0xfc, 0xa5, 0x0a],
+                [0xfc, 0xff, 0xa4],
+            ],
+        }
+    }
+
+    /// Colour at position `t` between 0 and 1
+    pub fn at(self, t: f64) -> egui::Color32 {
+        let stops = self.stops();
+        let pos = t.clamp(0., 1.) * (stops.len() - 1) as f64;
+        let idx = clamp_max(pos.floor() as usize, stops.len() - 2);
+        let frac = pos - idx as f64;
+        let [r, g, b] = std::array::from_fn(|i| {
+            let [lower, upper] = [stops[idx][i], stops[idx + 1][i]];
+            (lower as f64 + frac * (upper as f64 - lower as f64)).round() as u8
+        });
+        egui::Color32::from_rgb(r, g, b)
+    }
+}
+
+/// Colour particles by their transverse momentum instead of their species
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PtColours {
+    pub enabled: bool,
+    pub map: ColourMap,
+    /// Transverse momenta at the ends of the colour map
+    ///
+    /// Values in between are mapped logarithmically.
+    pub pt_range: [f64; 2],
+}
+
+impl Default for PtColours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            map: ColourMap::default(),
+            pt_range: [1., 1000.],
+        }
+    }
+}
+
+impl PtColours {
+    pub fn colour(&self, pt: f64) -> egui::Color32 {
+        let [min, max] = self.pt_range.map(f64::log10);
+        let t = if max > min {
+            (pt.log10() - min) / (max - min)
+        } else {
+            0.
+        };
+        self.map.at(t)
+    }
+
+    /// Show a horizontal colour bar with the transverse momentum range
+    pub fn colour_bar(&self, ui: &mut Ui) {
+        const NUM_STEPS: usize = 64;
+        const WIDTH: f32 = 160.;
+        let [min, max] = self.pt_range;
+        ui.horizontal(|ui| {
+            ui.label(format!("pT: {min} GeV"));
+            let height = ui.spacing().interact_size.y;
+            let (rect, _) = ui.allocate_exact_size(
+                egui::vec2(WIDTH, height),
+                egui::Sense::hover(),
+            );
+            let step = WIDTH / NUM_STEPS as f32;
+            for i in 0..NUM_STEPS {
+                let left = rect.left() + i as f32 * step;
+                let t = (i as f64 + 0.5) / NUM_STEPS as f64;
+                ui.painter().rect_filled(
+                    egui::Rect::from_x_y_ranges(
+                        left..=left + step,
+                        rect.y_range(),
+                    ),
+                    0.,
+                    self.map.at(t),
+                );
+            }
+            ui.label(format!("{max} GeV"));
+        });
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Projection {
     pub yaw: f64,
@@ -196,6 +611,11 @@ pub struct Settings3D {
     pub projection: Projection,
     // TODO: might be better to merge this into projection
     pub rotation: Rotation3<f64>,
+    #[serde(default)]
+    pub detector: DetectorGeometry,
+    /// Fade momentum lines in from the origin outward
+    #[serde(default)]
+    pub gradient: bool,
 }
 
 impl Default for Settings3D {
@@ -207,66 +627,329 @@ impl Default for Settings3D {
                 scale: 1.0,
             },
             rotation: Rotation3::identity(),
+            detector: Default::default(),
+            gradient: false,
+        }
+    }
+}
+
+/// Simplified detector made of concentric cylinders around the beam axis
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DetectorGeometry {
+    pub enabled: bool,
+    pub layers: Vec<DetectorLayer>,
+    /// Solenoid field strength in tesla, bending charged tracks in the
+    /// transverse view
+    #[serde(default = "default_field_strength")]
+    pub field_strength: f64,
+}
+
+impl Default for DetectorGeometry {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            layers: vec![
+                DetectorLayer {
+                    name: "Tracker".to_owned(),
+                    radius: 0.25,
+                    half_length: 0.5,
+                    colour: egui::Color32::from_rgb(120, 200, 120),
+                },
+                DetectorLayer {
+                    name: "Calorimeter".to_owned(),
+                    radius: 0.6,
+                    half_length: 0.9,
+                    colour: egui::Color32::from_rgb(220, 160, 80),
+                },
+            ],
+            field_strength: default_field_strength(),
+        }
+    }
+}
+
+fn default_field_strength() -> f64 {
+    2.
+}
+
+/// A cylindrical detector layer
+///
+/// Dimensions are in units of the 3D plot, where the axes range
+/// from -1 to 1.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct DetectorLayer {
+    pub name: String,
+    pub radius: f64,
+    pub half_length: f64,
+    pub colour: egui::Color32,
+}
+
+impl DetectorLayer {
+    /// Fraction of the line from the origin to `end` inside the layer
+    fn crossing(&self, end: &Point3<f64>) -> f64 {
+        let rho = end[0].hypot(end[1]);
+        let t_r = self.radius / rho;
+        let t_z = self.half_length / end[2].abs();
+        f64::min(t_r, t_z)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct JetHighlight {
+    pub jet: PseudoJet,
+    pub constituents: Vec<Particle>,
+    pub colour: egui::Color32,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct MergePreview {
+    pub enabled: bool,
+    /// Maximum ΔR for a pair of particles to be connected.
+    /// If `None`, the current jet radius is used.
+    pub max_delta_r: Option<f64>,
+    pub colour: egui::Color32,
+}
+
+impl Default for MergePreview {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_delta_r: None,
+            colour: egui::Color32::from_rgba_premultiplied(100, 100, 100, 80),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Plotter {
     pub r_jet: f64,
 
     pub settings: Settings,
     pub settings_3d: Settings3D,
+    #[serde(default)]
+    pub merge_preview: MergePreview,
+    /// Combine overlapping markers in the y-φ plot
+    #[serde(default)]
+    pub declutter: bool,
+    /// Draw incoming particles in the y-φ and 3D plots
+    #[serde(default)]
+    pub show_incoming: bool,
+    /// Label jets with their rank in transverse momentum and their pT
+    #[serde(default)]
+    pub label_jets: bool,
+    /// Draw guides for the beam axis and the interaction point
+    #[serde(default = "default_show_beam_axis")]
+    pub show_beam_axis: bool,
+    /// Maximum number of periodic images drawn in the y-φ plot
+    #[serde(default = "default_max_phi_images")]
+    pub max_phi_images: usize,
+    /// Number of φ periods shown in the current y-φ plot
+    ///
+    /// If `None`, periodic images fill the whole plot.
+    #[serde(skip)]
+    pub phi_periods: Option<f64>,
+    /// Temporary colour override for a single jet and its constituents
+    #[serde(skip)]
+    pub highlight: Option<JetHighlight>,
+    /// Constituents of the jets in the current event
+    #[serde(skip)]
+    pub constituents: Vec<(PseudoJet, Vec<Particle>)>,
+    /// Jet under the mouse pointer in the plot that is being drawn
+    #[serde(skip)]
+    hovered_jet: Option<PseudoJet>,
+    /// Number of markers drawn since the last reset, for diagnostics
+    #[serde(skip)]
+    pub drawn_markers: usize,
+    /// Proto-jets combined in the last step of a clustering sequence
+    #[serde(skip)]
+    pub last_merge: Option<[PseudoJet; 2]>,
+    /// Particles selected with the keyboard or mouse
+    #[serde(skip)]
+    pub selected: Vec<Particle>,
+    /// Reset the bounds of all plots drawn in this frame
+    #[serde(skip)]
+    pub reset_view: bool,
+}
+
+impl Default for Plotter {
+    fn default() -> Self {
+        Self {
+            r_jet: Default::default(),
+            settings: Default::default(),
+            settings_3d: Default::default(),
+            merge_preview: Default::default(),
+            declutter: false,
+            show_incoming: false,
+            label_jets: false,
+            show_beam_axis: default_show_beam_axis(),
+            max_phi_images: default_max_phi_images(),
+            phi_periods: None,
+            highlight: None,
+            constituents: Vec::new(),
+            hovered_jet: None,
+            drawn_markers: 0,
+            last_merge: None,
+            selected: Vec::new(),
+            reset_view: false,
+        }
+    }
+}
+
+fn default_show_beam_axis() -> bool {
+    true
+}
+
+fn default_max_phi_images() -> usize {
+    16
 }
 
 impl Plotter {
+    #[allow(clippy::too_many_arguments)]
     pub fn plot_y_phi(
         &mut self,
         ui: &mut Ui,
         event: &Event,
         jets: &[PseudoJet],
+        y_scale: YScale,
+        show_missing_pt: bool,
+        y_range: Option<Range<f64>>,
+        phi_periods: Option<f64>,
     ) -> Option<PlotResponse> {
         use PlotResponse::*;
         let mut response = None;
-        let [y_min, y_max] = y_min_max(&event.out);
-        Plot::new("y phi plot")
-            .include_x(y_min)
+        self.phi_periods = phi_periods;
+        let phi_axis_max = phi_periods.map_or(PHI_AXIS_MAX, |p| 2. * p);
+        let particles = self.settings.visible_particles(event);
+        let rapidity = self.settings.rapidity;
+        let [y_min, y_max] = match &y_range {
+            Some(range) => {
+                [range.start, range.end].map(|y| y_scale.to_coord(y))
+            }
+            None => y_scale.axis_range(y_min_max(&particles, rapidity)),
+        };
+        let jet_axes = Vec::from_iter(
+            jets.iter()
+                .map(|jet| (rapidity.of_jet(jet), f64::from(jet.phi()))),
+        );
+        let jet_centres = jet_axes.clone();
+        let missing_pt = show_missing_pt.then(|| {
+            let [px, py] = event.missing_pt();
+            [px.hypot(py), py.atan2(px)]
+        });
+        if self.settings.pt_colours.enabled {
+            self.settings.pt_colours.colour_bar(ui);
+        }
+        self.settings.apply_plot_colours(ui);
+        // for looking up the hovered particle in the label formatter
+        let hover_particles = particles.clone();
+        let mut plot = Plot::new("y phi plot");
+        if y_scale == YScale::Compressed {
+            plot = plot.x_axis_formatter(y_compressed_tick_label);
+        }
+        plot = if phi_periods.is_some() {
+            plot.y_axis_formatter(unwrapped_phi_tick_label)
+        } else {
+            plot.y_axis_formatter(phi_tick_label)
+        };
+        if self.reset_view {
+            plot = plot.reset();
+        }
+        plot.include_x(y_min)
             .include_x(y_max)
-            .include_y(PHI_AXIS_MIN)
-            .include_y(PHI_AXIS_MAX)
-            .auto_bounds([true, false].into())
-            .x_axis_label("y")
+            .include_y(-phi_axis_max)
+            .include_y(phi_axis_max)
+            .auto_bounds([y_range.is_none(), false].into())
+            .x_axis_label(rapidity.to_string())
             .y_axis_label("φ")
-            .y_axis_formatter(phi_tick_label)
             .show_grid([false, false])
             .legend(Legend::default())
-            .label_formatter(|name, val| {
-                let y = val.x;
+            .label_formatter(move |name, val| {
+                let y = y_scale.inverse(val.x);
                 let phi = clamp_phi_coord(val.y) * PHI_SCALE;
-                format!("{name}\ny = {y:.2}\nφ = {phi:.2}")
+                if let (MISSING_PT, Some([met, phi])) = (name, missing_pt) {
+                    return format!("{name} = {met:.2}\nφ = {phi:.2}");
+                }
+                if name.starts_with(INCOMING) {
+                    return name.to_owned();
+                }
+                if name.is_empty() || name == "jet" {
+                    return format!("{name}\n{rapidity} = {y:.2}\nφ = {phi:.2}");
+                }
+                let delta_r = jet_axes
+                    .iter()
+                    .map(|&(jet_y, jet_phi)| {
+                        let dy = y - jet_y;
+                        let dphi = delta_phi(phi, jet_phi);
+                        (dy * dy + dphi * dphi).sqrt()
+                    })
+                    .min_by(|a, b| a.total_cmp(b));
+                let delta_r = delta_r
+                    .map(|dr| format!("{dr:.2}"))
+                    .unwrap_or_else(|| "—".to_owned());
+                let details = nearest_particle(&hover_particles, |p| {
+                    let dy = val.x - y_scale.to_coord(rapidity.of(p));
+                    let dphi = clamp_phi_coord(val.y - p.phi / PHI_SCALE);
+                    dy * dy + dphi * dphi
+                })
+                .map(particle_details)
+                .unwrap_or_default();
+                format!("{name}\n{rapidity} = {y:.2}\nφ = {phi:.2}\nΔR(jet) = {delta_r}{details}")
             })
             .show(ui, |ui| {
-                for particle in &event.out {
-                    self.draw_y_phi(ui, particle);
+                self.hovered_jet = ui.pointer_coordinate().and_then(|pos| {
+                    let y = y_scale.inverse(pos.x);
+                    let phi = clamp_phi_coord(pos.y) * PHI_SCALE;
+                    jet_at(&jet_centres, y, phi, self.r_jet).map(|n| jets[n])
+                });
+                if self.show_beam_axis {
+                    draw_beam_axis(ui, y_scale.to_coord(0.));
+                }
+                if self.merge_preview.enabled {
+                    self.draw_y_phi_merge_candidates(ui, &particles, y_scale);
                 }
-                for jet in jets {
-                    self.draw_y_phi_jet(ui, jet);
+                if self.declutter {
+                    self.draw_y_phi_decluttered(ui, &particles, y_scale);
+                } else {
+                    for particle in &particles {
+                        self.draw_y_phi(ui, particle, y_scale);
+                    }
+                }
+                for (idx, jet) in jets.iter().enumerate() {
+                    self.draw_y_phi_jet(ui, idx, jet, y_scale);
+                }
+                if self.label_jets {
+                    for (rank, jet) in pt_ranks(jets).into_iter().zip(jets) {
+                        self.draw_y_phi_jet_label(ui, rank, jet, y_scale);
+                    }
+                }
+                if let Some(merged) = self.last_merge {
+                    self.draw_y_phi_merge(ui, merged, y_scale);
+                }
+                if let Some([_, phi]) = missing_pt {
+                    self.draw_y_phi_missing_pt(ui, phi);
+                }
+                if self.show_incoming {
+                    for particle in &event.incoming {
+                        self.draw_y_phi_incoming(ui, particle);
+                    }
                 }
                 let ui_response = ui.response();
                 if ui_response.clicked() {
                     // TODO: better account for zoom levels etc.
                     let click_pos = ui_response.interact_pointer_pos().unwrap();
                     let click_pos = ui.plot_from_screen(click_pos).to_pos2();
-                    // TODO: periodicity
                     debug!("Click at {click_pos:?}");
                     let mut closest_dist = f32::MAX;
-                    let Some(mut closest) = event.out.first() else {
+                    let Some(mut closest) = particles.first() else {
                         return;
                     };
-                    for particle in event.out.iter() {
+                    for particle in particles.iter() {
+                        let y_coord = y_scale.to_coord(rapidity.of(particle));
                         let phi_coord = particle.phi / PHI_SCALE;
-                        let pos = [particle.y as f32, phi_coord as f32].into();
-                        let dist = click_pos.distance_sq(pos);
+                        // distance to the closest periodic image
+                        let dy = click_pos.x - y_coord as f32;
+                        let dphi =
+                            clamp_phi_coord(click_pos.y as f64 - phi_coord);
+                        let dist = dy * dy + (dphi * dphi) as f32;
                         if dist < closest_dist {
                             closest_dist = dist;
                             closest = particle;
@@ -275,7 +958,13 @@ impl Plotter {
                     debug!("At distance^2 {closest_dist}: {closest:#?}");
                     const MAX_DIST: f32 = 0.13;
                     if closest_dist < MAX_DIST {
-                        response = Some(Selected(*closest));
+                        response = Some(select(ui, *closest));
+                        return;
+                    }
+                    let y = y_scale.inverse(click_pos.x as f64);
+                    let phi = clamp_phi_coord(click_pos.y as f64) * PHI_SCALE;
+                    if let Some(n) = jet_at(&jet_centres, y, phi, self.r_jet) {
+                        response = Some(SelectedJet(jets[n]));
                     }
                 } else {
                     ui_response.clone().context_menu(|ui| {
@@ -288,6 +977,7 @@ impl Plotter {
                     });
                 }
             });
+        self.hovered_jet = None;
         response
     }
 
@@ -296,48 +986,91 @@ impl Plotter {
         ui: &mut Ui,
         event: &Event,
         jets: &[PseudoJet],
+        pt_scale: PtScale,
+        fixed_range: Option<(Range<f64>, Range<f64>)>,
     ) -> Option<PlotResponse> {
-        use PlotResponse::*;
         let mut response = None;
-        let max_logpt = event
-            .out
-            .iter()
-            .map(|p| p.pt.log10())
-            .min_by(|a, b| b.partial_cmp(a).unwrap())
-            .unwrap_or_default();
-        let min_logpt = event
-            .out
-            .iter()
-            .map(|p| p.pt.log10())
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or_default();
-        let min_logpt = clamp_max(min_logpt, max_logpt - 1.0);
-        let range = max_logpt - min_logpt;
-        let min_logpt = min_logpt - 0.1 * range;
-        let max_logpt = max_logpt + 0.1 * range;
-        let [y_min, y_max] = y_min_max(&event.out);
-        Plot::new("y logpt plot")
-            .include_x(y_min)
+        let particles = self.settings.visible_particles(event);
+        let rapidity = self.settings.rapidity;
+        let auto_bounds = fixed_range.is_none();
+        let ([y_min, y_max], [min_pt_coord, max_pt_coord]) = match fixed_range {
+            Some((y, logpt)) => {
+                let pt_coord =
+                    |logpt: f64| pt_scale.to_coord(10f64.powf(logpt));
+                (
+                    [y.start, y.end],
+                    [pt_coord(logpt.start), pt_coord(logpt.end)],
+                )
+            }
+            None => (
+                y_min_max(&particles, rapidity),
+                pt_scale.axis_range(&particles),
+            ),
+        };
+        if self.settings.pt_colours.enabled {
+            self.settings.pt_colours.colour_bar(ui);
+        }
+        self.settings.apply_plot_colours(ui);
+        // for looking up the hovered particle in the label formatter
+        let hover_particles = particles.clone();
+        let mut plot = Plot::new("y logpt plot");
+        if self.reset_view {
+            plot = plot.reset();
+        }
+        if pt_scale == PtScale::Log {
+            plot = plot.y_axis_formatter(logpt_tick_label);
+        }
+        plot.include_x(y_min)
             .include_x(y_max)
-            .include_y(min_logpt)
-            .include_y(max_logpt)
-            .auto_bounds([true, false].into())
-            .x_axis_label("y")
+            .include_y(min_pt_coord)
+            .include_y(max_pt_coord)
+            .auto_bounds([auto_bounds, false].into())
+            .x_axis_label(rapidity.to_string())
             .y_axis_label("pT")
-            .y_axis_formatter(logpt_tick_label)
             .show_grid([false, false])
             .legend(Legend::default())
-            .label_formatter(|name, val| {
+            .label_formatter(move |name, val| {
                 let y = val.x;
-                let pt = 10f64.powf(val.y);
-                format!("{name}\ny = {y:.2}\npT = {pt:.2}")
+                let pt = pt_scale.inverse(val.y);
+                if name.is_empty() || name == "jet" {
+                    return format!(
+                        "{name}\n{rapidity} = {y:.2}\npT = {pt:.2}"
+                    );
+                }
+                let details = nearest_particle(&hover_particles, |p| {
+                    let dy = val.x - rapidity.of(p);
+                    let dpt = val.y - pt_scale.to_coord(p.pt);
+                    dy * dy + dpt * dpt
+                })
+                .map(particle_details)
+                .unwrap_or_default();
+                format!("{name}\n{rapidity} = {y:.2}\npT = {pt:.2}{details}")
             })
             .show(ui, |ui| {
-                for jet in jets {
-                    self.draw_y_logpt_jet(ui, jet);
+                self.hovered_jet = ui.pointer_coordinate().and_then(|pos| {
+                    // innermost of the rectangles below the pointer
+                    jets.iter()
+                        .filter(|jet| {
+                            let dy = pos.x - rapidity.of_jet(jet);
+                            let pt = pt_scale.to_coord(jet.pt().into());
+                            dy.abs() < self.r_jet && pos.y < pt
+                        })
+                        .min_by(|a, b| a.pt2().cmp(&b.pt2()))
+                        .copied()
+                });
+                if self.show_beam_axis {
+                    draw_beam_axis(ui, 0.);
                 }
-                for particle in &event.out {
-                    self.draw_y_logpt(ui, particle);
+                for (idx, jet) in jets.iter().enumerate() {
+                    self.draw_y_logpt_jet(ui, idx, jet, pt_scale);
+                }
+                if self.label_jets {
+                    for (rank, jet) in pt_ranks(jets).into_iter().zip(jets) {
+                        self.draw_y_logpt_jet_label(ui, rank, jet, pt_scale);
+                    }
+                }
+                for particle in &particles {
+                    self.draw_y_logpt(ui, particle, pt_scale);
                 }
                 let ui_response = ui.response();
                 if ui_response.clicked() {
@@ -346,12 +1079,13 @@ impl Plotter {
                     let click_pos = ui.plot_from_screen(click_pos).to_pos2();
                     debug!("Click at {click_pos:?}");
                     let mut closest_dist = f32::MAX;
-                    let Some(mut closest) = event.out.first() else {
+                    let Some(mut closest) = particles.first() else {
                         return;
                     };
-                    for particle in event.out.iter() {
-                        let pt_coord = particle.pt.log10();
-                        let pos = [particle.y as f32, pt_coord as f32].into();
+                    for particle in particles.iter() {
+                        let pt_coord = pt_scale.to_coord(particle.pt);
+                        let y = rapidity.of(particle);
+                        let pos = [y as f32, pt_coord as f32].into();
                         let dist = click_pos.distance_sq(pos);
                         if dist < closest_dist {
                             closest_dist = dist;
@@ -361,7 +1095,7 @@ impl Plotter {
                     debug!("At distance^2 {closest_dist}: {closest:#?}");
                     const MAX_DIST: f32 = 0.13;
                     if closest_dist < MAX_DIST {
-                        response = Some(Selected(*closest));
+                        response = Some(select(ui, *closest));
                     }
                 } else {
                     ui_response.clone().context_menu(|ui| {
@@ -374,22 +1108,29 @@ impl Plotter {
                     });
                 }
             });
+        self.hovered_jet = None;
         response
     }
 
     pub fn plot_3d(
         &mut self,
         event: &Event,
-        _jets: &[PseudoJet],
-        img: &mut Vec<u8>,
+        jets: &[PseudoJet],
+        img: &mut [u8],
         size: [usize; 2],
     ) -> Result<()> {
         use plotters::prelude::*;
         let [width, height] = size;
+        ensure!(
+            width > 0 && height > 0,
+            "Cannot draw 3D plot with size {width}×{height}"
+        );
         let backend =
             BitMapBackend::with_buffer(img, (width as u32, height as u32))
                 .into_drawing_area();
-        // root.fill(&to_plotters_col(self.colour.background))?;
+        if self.settings.background != egui::Color32::TRANSPARENT {
+            backend.fill(&to_plotters_col(self.settings.background))?;
+        }
         // let root = root.margin(10, 10, 10, 10);
         let range = (-1.0..1.0).step(0.1);
         {
@@ -458,23 +1199,344 @@ impl Plotter {
                 ))?;
             }
 
-            for out in &event.out {
+            if self.settings_3d.detector.enabled {
+                for (colour, line) in self.detector_lines() {
+                    chart.draw_series(LineSeries::new(line, colour))?;
+                }
+            }
+
+            if self.show_beam_axis {
+                let colour = to_plotters_col(self.settings.frame);
+                chart.draw_series(
+                    self.beam_axis_dashes()
+                        .into_iter()
+                        .map(|dash| PathElement::new(dash, colour.mix(0.5))),
+                )?;
+                chart.draw_series(std::iter::once(Circle::new(
+                    (0., 0., 0.),
+                    3,
+                    colour.mix(0.5).filled(),
+                )))?;
+            }
+
+            for out in self.settings.visible_particles(event) {
                 let mut coord = Point3::from([out.p[1], out.p[2], out.p[3]]);
                 for c in coord.iter_mut() {
                     *c = 2. / PI * c.atan()
                 }
+                if self.settings_3d.detector.enabled {
+                    let crossings = self.detector_crossings(&coord);
+                    chart.draw_series(crossings.into_iter().map(
+                        |(colour, pt)| Circle::new(pt, 2, colour.filled()),
+                    ))?;
+                }
                 coord = self.settings_3d.rotation * coord;
 
-                chart.draw_series(LineSeries::new(
-                    (0..=1).map(|t| {
-                        let t = t as f64;
-                        (t * coord[0], t * coord[1], t * coord[2])
-                    }),
-                    &to_plotters_col(self.get_particle_style(out.id).colour),
-                ))?;
+                // fade out intermediate particles
+                let alpha = if out.status == ParticleStatus::Intermediate {
+                    0.5
+                } else {
+                    1.
+                };
+                let colour =
+                    to_plotters_col(self.get_particle_style(out.id).colour)
+                        .mix(alpha);
+                if self.settings_3d.gradient {
+                    const NUM_SEGMENTS: usize = 16;
+                    let at =
+                        |t: f64| (t * coord[0], t * coord[1], t * coord[2]);
+                    chart.draw_series((0..NUM_SEGMENTS).map(|i| {
+                        let t0 = i as f64 / NUM_SEGMENTS as f64;
+                        let t1 = (i + 1) as f64 / NUM_SEGMENTS as f64;
+                        PathElement::new(vec![at(t0), at(t1)], colour.mix(t1))
+                    }))?;
+                } else {
+                    chart.draw_series(LineSeries::new(
+                        (0..=1).map(|t| {
+                            let t = t as f64;
+                            (t * coord[0], t * coord[1], t * coord[2])
+                        }),
+                        &colour,
+                    ))?;
+                }
+            }
+
+            if self.show_incoming {
+                for incoming in &event.incoming {
+                    // draw the line towards the interaction point
+                    let p = incoming.p;
+                    let mut coord = Point3::from([-p[1], -p[2], -p[3]]);
+                    for c in coord.iter_mut() {
+                        *c = 2. / PI * c.atan()
+                    }
+                    coord = self.settings_3d.rotation * coord;
+                    let colour = to_plotters_col(
+                        self.get_particle_style(incoming.id).colour,
+                    );
+                    chart.draw_series(LineSeries::new(
+                        [(coord[0], coord[1], coord[2]), (0., 0., 0.)],
+                        colour.mix(0.5),
+                    ))?;
+                }
+            }
+
+            for (idx, jet) in jets.iter().enumerate() {
+                let colour = to_plotters_col(self.settings.jet_colour(idx));
+                for (alpha, line) in self.jet_cone_lines(jet) {
+                    chart.draw_series(LineSeries::new(
+                        line,
+                        colour.mix(alpha),
+                    ))?;
+                }
+            }
+        }
+
+        let frame = to_plotters_col(self.settings.frame);
+        let corner = [width, height].map(|n| n.saturating_sub(1) as i32);
+        let corner = (corner[0], corner[1]);
+        backend.draw(&Rectangle::new([(0, 0), corner], frame))?;
+        Ok(())
+    }
+
+    /// Axis, rim, and a few generating lines of a jet cone in the 3D plot
+    ///
+    /// Each line is returned together with its opacity.
+    fn jet_cone_lines(&self, jet: &PseudoJet) -> Vec<(f64, Line3)> {
+        const NUM_SEGMENTS: usize = 32;
+        const NUM_LINES: usize = 8;
+        let rot = self.settings_3d.rotation;
+        let pt = f64::from(jet.pt());
+        let [y, phi] = [f64::from(jet.rap()), f64::from(jet.phi())];
+        let to_coord = |y: f64, phi: f64| {
+            let mut coord =
+                Point3::from([pt * phi.cos(), pt * phi.sin(), pt * y.sinh()]);
+            for c in coord.iter_mut() {
+                *c = 2. / PI * c.atan()
+            }
+            let coord = rot * coord;
+            (coord[0], coord[1], coord[2])
+        };
+        let rim_pt = |t: usize, nsegments: usize| {
+            let theta = 2. * PI * (t as f64) / (nsegments as f64);
+            to_coord(
+                y + self.r_jet * theta.cos(),
+                phi + self.r_jet * theta.sin(),
+            )
+        };
+        let origin = (0., 0., 0.);
+        let mut lines = vec![
+            (1., vec![origin, to_coord(y, phi)]),
+            (
+                0.8,
+                Vec::from_iter(
+                    (0..=NUM_SEGMENTS).map(|t| rim_pt(t, NUM_SEGMENTS)),
+                ),
+            ),
+        ];
+        lines.extend(
+            (0..NUM_LINES).map(|t| (0.4, vec![origin, rim_pt(t, NUM_LINES)])),
+        );
+        lines
+    }
+
+    /// Dashes along the beam axis in the 3D plot
+    fn beam_axis_dashes(&self) -> Vec<Line3> {
+        const NUM_DASHES: usize = 20;
+        let rot = self.settings_3d.rotation;
+        let to_coord = |z: f64| {
+            let pt = rot * Point3::from([0., 0., z]);
+            (pt[0], pt[1], pt[2])
+        };
+        // every other of 2 * NUM_DASHES segments between z = -1 and z = 1
+        (0..NUM_DASHES)
+            .map(|i| {
+                let z = -1. + 2. * i as f64 / NUM_DASHES as f64;
+                vec![to_coord(z), to_coord(z + 1. / NUM_DASHES as f64)]
+            })
+            .collect()
+    }
+
+    /// Wireframe lines for the detector layers in the 3D plot
+    fn detector_lines(&self) -> Vec<(RGBAColor, Line3)> {
+        use plotters::style::Color;
+        const NUM_SEGMENTS: usize = 48;
+        const NUM_LINES: usize = 12;
+        let rot = self.settings_3d.rotation;
+        let to_coord = |pt: Point3<f64>| {
+            let pt = rot * pt;
+            (pt[0], pt[1], pt[2])
+        };
+        let mut lines = Vec::new();
+        for layer in &self.settings_3d.detector.layers {
+            let colour = to_plotters_col(layer.colour).mix(0.4);
+            let r = layer.radius;
+            let l = layer.half_length;
+            for z in [-l, l] {
+                let circle = (0..=NUM_SEGMENTS).map(|t| {
+                    let phi = 2. * PI * (t as f64) / (NUM_SEGMENTS as f64);
+                    to_coord([r * phi.cos(), r * phi.sin(), z].into())
+                });
+                lines.push((colour, circle.collect()));
+            }
+            for t in 0..NUM_LINES {
+                let phi = 2. * PI * (t as f64) / (NUM_LINES as f64);
+                let [x, y] = [r * phi.cos(), r * phi.sin()];
+                let line = [-l, l].map(|z| to_coord([x, y, z].into()));
+                lines.push((colour, line.to_vec()));
+            }
+        }
+        lines
+    }
+
+    /// Points where the line from the origin to `end` leaves each detector layer
+    fn detector_crossings(
+        &self,
+        end: &Point3<f64>,
+    ) -> Vec<(RGBAColor, (f64, f64, f64))> {
+        let mut crossings = Vec::new();
+        for layer in &self.settings_3d.detector.layers {
+            let t = layer.crossing(end);
+            if t >= 1. {
+                continue;
             }
+            let pt = self.settings_3d.rotation * (end * t);
+            let colour = to_plotters_col(layer.colour);
+            crossings.push((colour, (pt[0], pt[1], pt[2])));
         }
+        crossings
+    }
+
+    /// Offsets of the periodic images in the y-φ plot
+    ///
+    /// Only images overlapping the φ range of `phi_periods` are kept.
+    fn phi_shifts(&self, bounds: PlotBounds) -> impl Iterator<Item = f64> {
+        // images cover φ coordinates in [shift - 2, shift + 2]
+        let max_shift = self.phi_periods.map_or(f64::INFINITY, |p| 2. * p + 2.);
+        phi_shifts(bounds, self.max_phi_images)
+            .filter(move |shift| shift.abs() < max_shift)
+    }
+
+    /// Draw a slice transverse to the beam axis
+    ///
+    /// Particles and jets are drawn as lines from the beam spot in the
+    /// direction of their azimuthal angle, with a length proportional to
+    /// their transverse momentum. Returns the transverse momentum
+    /// corresponding to unit length.
+    ///
+    /// If the detector has a magnetic field, charged particles are
+    /// instead drawn as tracks bending in the field, see `charged_track`.
+    pub fn plot_transverse(
+        &mut self,
+        event: &Event,
+        jets: &[PseudoJet],
+        img: &mut [u8],
+        size: [usize; 2],
+    ) -> Result<f64> {
+        use plotters::prelude::*;
+        const NUM_SEGMENTS: usize = 96;
+        let [width, height] = size;
+        let backend =
+            BitMapBackend::with_buffer(img, (width as u32, height as u32))
+                .into_drawing_area();
+        if self.settings.background != egui::Color32::TRANSPARENT {
+            backend.fill(&to_plotters_col(self.settings.background))?;
+        }
+        let mut chart = ChartBuilder::on(&backend)
+            .margin(5)
+            .build_cartesian_2d(-1.05..1.05, -1.05..1.05)?;
+        for layer in &self.settings_3d.detector.layers {
+            let r = layer.radius;
+            let circle = (0..=NUM_SEGMENTS).map(|t| {
+                let phi = 2. * PI * (t as f64) / (NUM_SEGMENTS as f64);
+                (r * phi.cos(), r * phi.sin())
+            });
+            let colour = to_plotters_col(layer.colour);
+            chart.draw_series(LineSeries::new(circle, colour))?;
+        }
+        let frame = to_plotters_col(self.settings.frame);
+        chart.draw_series(std::iter::once(Circle::new(
+            (0., 0.),
+            3,
+            frame.filled(),
+        )))?;
 
+        let particles = self.settings.visible_particles(event);
+        let pt_max = particles
+            .iter()
+            .map(|p| p.pt)
+            .chain(jets.iter().map(|jet| jet.pt().into()))
+            .fold(0., f64::max);
+        if pt_max <= 0. {
+            return Ok(pt_max);
+        }
+        let track = |phi: f64, pt: f64| {
+            let r = pt / pt_max;
+            vec![(0., 0.), (r * phi.cos(), r * phi.sin())]
+        };
+        for (idx, jet) in jets.iter().enumerate() {
+            let colour = to_plotters_col(self.jet_colour(idx, jet));
+            chart.draw_series(std::iter::once(PathElement::new(
+                track(jet.phi().into(), jet.pt().into()),
+                colour.stroke_width(8),
+            )))?;
+        }
+        let detector = &self.settings_3d.detector;
+        let field = detector.field_strength;
+        let r_max = detector
+            .layers
+            .iter()
+            .map(|layer| layer.radius)
+            .fold(0., f64::max);
+        let r_max = if r_max > 0. { r_max } else { 1. };
+        for particle in &particles {
+            let style = self.get_particle_style(particle.id);
+            let colour = to_plotters_col(style.colour);
+            let charge = particle.charge();
+            let points = if field > 0. && charge != 0. {
+                charged_track(particle.phi, particle.pt, field * charge, r_max)
+            } else {
+                track(particle.phi, particle.pt)
+            };
+            chart.draw_series(std::iter::once(PathElement::new(
+                points,
+                colour.stroke_width(2),
+            )))?;
+        }
+        Ok(pt_max)
+    }
+
+    /// Draw a small y-φ plot without axes or jets
+    pub fn plot_y_phi_thumbnail(
+        &mut self,
+        event: &Event,
+        img: &mut [u8],
+        size: [usize; 2],
+    ) -> Result<()> {
+        use plotters::prelude::*;
+        let [width, height] = size;
+        let backend =
+            BitMapBackend::with_buffer(img, (width as u32, height as u32))
+                .into_drawing_area();
+        let particles = self.settings.visible_particles(event);
+        let [y_min, y_max] = y_min_max(&particles, Rapidity::Y);
+        let mut chart = ChartBuilder::on(&backend)
+            .margin(2)
+            .build_cartesian_2d(y_min..y_max, -PI..PI)?;
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_labels(0)
+            .y_labels(0)
+            .draw()?;
+        for particle in &particles {
+            let style = self.get_particle_style(particle.id);
+            let col = to_plotters_col(style.colour);
+            chart.draw_series(std::iter::once(Circle::new(
+                (particle.y, particle.phi),
+                2,
+                col.filled(),
+            )))?;
+        }
         Ok(())
     }
 
@@ -488,41 +1550,191 @@ impl Plotter {
     fn draw_particle_at(
         &mut self,
         ui: &mut egui_plot::PlotUi,
-        particle_id: ParticleID,
+        particle: &Particle,
         centre: [f64; 2],
     ) {
+        let particle_id = particle.id;
         let ParticleStyle {
-            colour,
+            mut colour,
             shape,
             size,
+            outline,
+            outline_width,
         } = self.get_particle_style(particle_id);
+        let size = self.settings.marker_size.of(particle, size);
+        if self.settings.pt_colours.enabled {
+            colour = self.settings.pt_colours.colour(particle.pt);
+        }
+        if let Some(highlight) = &self.highlight {
+            if highlight.constituents.contains(particle) {
+                colour = highlight.colour;
+            }
+        }
+        let in_hovered_jet = self.is_in_hovered_jet(particle);
+        let size = if in_hovered_jet {
+            HOVER_ENLARGEMENT * size
+        } else {
+            size
+        };
+        // hollow markers set intermediate particles apart
+        let filled = particle.status != ParticleStatus::Intermediate;
+        if outline_width > 0. && filled {
+            // slightly larger marker behind the actual one
+            let outline = Points::new(centre)
+                .color(outline)
+                .radius(size + outline_width)
+                .shape(shape.into())
+                .highlight(true);
+            ui.points(outline);
+        }
         let mut pt = Points::new(centre)
             .color(colour)
             .radius(size)
             .shape(shape.into())
+            .filled(filled)
             .highlight(true);
         if let Some(name) = particle_id.symbol() {
             pt = pt.name(name);
         }
         ui.points(pt);
+        if in_hovered_jet {
+            let outline = Points::new(centre)
+                .color(ui.ctx().style().visuals.strong_text_color())
+                .radius(size + 1.)
+                .shape(shape.into())
+                .filled(false)
+                .highlight(true);
+            ui.points(outline);
+        }
+        if self.selected.contains(particle) {
+            let ring = Points::new(centre)
+                .color(ui.ctx().style().visuals.strong_text_color())
+                .radius(size + 3.)
+                .shape(egui_plot::MarkerShape::Circle)
+                .filled(false);
+            ui.points(ring);
+        }
+        self.drawn_markers += 1;
+    }
+
+    /// Check whether `particle` belongs to the jet under the mouse pointer
+    fn is_in_hovered_jet(&self, particle: &Particle) -> bool {
+        let Some(hovered) = &self.hovered_jet else {
+            return false;
+        };
+        self.constituents
+            .iter()
+            .find(|(jet, _)| jet == hovered)
+            .is_some_and(|(_, constituents)| constituents.contains(particle))
     }
 
-    fn draw_y_phi(&mut self, ui: &mut egui_plot::PlotUi, particle: &Particle) {
+    fn draw_y_phi(
+        &mut self,
+        ui: &mut egui_plot::PlotUi,
+        particle: &Particle,
+        y_scale: YScale,
+    ) {
         let Particle { id, y, phi, .. } = particle;
 
         debug!("Drawing particle {} at (y, φ) = ({y}, {phi})", id.id());
-        let mut phi_min = ui.plot_bounds().min()[1].floor() as i64;
-        phi_min -= phi_min % 4;
-        let phi_max = ui.plot_bounds().max()[1];
-        let mut centre = [*y, phi_min as f64 + *phi / PHI_SCALE];
-        while centre[1] < phi_max {
-            self.draw_particle_at(ui, *id, centre);
-            centre[1] += 4.0
+        let y_coord = y_scale.to_coord(self.settings.rapidity.of(particle));
+        for shift in self.phi_shifts(ui.plot_bounds()) {
+            let centre = [y_coord, shift + *phi / PHI_SCALE];
+            self.draw_particle_at(ui, particle, centre);
         }
     }
 
-    fn draw_y_phi_jet(&self, ui: &mut egui_plot::PlotUi, jet: &PseudoJet) {
-        let y: f64 = jet.rap().into();
+    /// Draw particles, combining markers that are very close on screen
+    fn draw_y_phi_decluttered(
+        &mut self,
+        ui: &mut egui_plot::PlotUi,
+        particles: &[Particle],
+        y_scale: YScale,
+    ) {
+        // maximum screen distance in points for combining markers
+        const MAX_DIST: f32 = 4.;
+        let mut groups: Vec<(egui::Pos2, Vec<&Particle>)> = Vec::new();
+        for particle in particles {
+            let y = self.settings.rapidity.of(particle);
+            let coord = [y_scale.to_coord(y), particle.phi / PHI_SCALE];
+            let pos = ui.screen_from_plot(coord.into());
+            let group = groups
+                .iter_mut()
+                .find(|(centre, _)| centre.distance(pos) < MAX_DIST);
+            match group {
+                Some((_, members)) => members.push(particle),
+                None => groups.push((pos, vec![particle])),
+            }
+        }
+        let pointer = ui.ctx().input(|i| i.pointer.hover_pos());
+        for (_, members) in groups {
+            if let [particle] = members.as_slice() {
+                self.draw_y_phi(ui, particle, y_scale);
+                continue;
+            }
+            let n = members.len() as f64;
+            let y = members
+                .iter()
+                .map(|p| y_scale.to_coord(self.settings.rapidity.of(p)))
+                .sum::<f64>();
+            let y = y / n;
+            // average relative to the first particle to respect periodicity
+            let phi0 = members[0].phi;
+            let dphi =
+                members.iter().map(|p| delta_phi(p.phi, phi0)).sum::<f64>();
+            let phi = phi0 + dphi / n;
+            let style = self.get_particle_style(members[0].id);
+            let size = members
+                .iter()
+                .map(|p| self.settings.marker_size.of(p, style.size))
+                .fold(0., f32::max);
+            let radius = size * (members.len() as f32).sqrt();
+            let colour = if self.settings.pt_colours.enabled {
+                let pt = members.iter().map(|p| p.pt).sum();
+                self.settings.pt_colours.colour(pt)
+            } else {
+                style.colour
+            };
+            for shift in self.phi_shifts(ui.plot_bounds()) {
+                let centre = [y, shift + phi / PHI_SCALE];
+                let pt = Points::new(centre)
+                    .color(colour)
+                    .radius(radius)
+                    .shape(egui_plot::MarkerShape::Circle);
+                ui.points(pt);
+                let pos = ui.screen_from_plot(centre.into());
+                let hovered =
+                    pointer.is_some_and(|p| p.distance(pos) <= radius);
+                if hovered {
+                    egui::show_tooltip_at_pointer(
+                        ui.ctx(),
+                        egui::Id::new("combined markers"),
+                        |ui| {
+                            for p in &members {
+                                ui.label(format!(
+                                    "{}: {} = {:.2}, φ = {:.2}, pT = {:.2}",
+                                    p.name(),
+                                    self.settings.rapidity,
+                                    self.settings.rapidity.of(p),
+                                    p.phi,
+                                    p.pt
+                                ));
+                            }
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn draw_y_phi_jet(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        idx: usize,
+        jet: &PseudoJet,
+        y_scale: YScale,
+    ) {
+        let y = self.settings.rapidity.of_jet(jet);
         let mut phi: f64 = jet.phi().into();
         if phi > PI {
             phi -= 2.0 * PI;
@@ -531,27 +1743,155 @@ impl Plotter {
             "Drawing jet with radius {} at (y, φ) = ({y}, {phi})",
             self.r_jet
         );
-        let mut phi_min = ui.plot_bounds().min()[1].floor() as i64;
-        phi_min -= phi_min % 4;
-        let phi_max = ui.plot_bounds().max()[1];
-        let mut centre = [y, phi_min as f64 + phi / PHI_SCALE];
-        while centre[1] < phi_max {
-            self.draw_jet_circle(ui, centre);
-            centre[1] += 4.0
+        let jet_col = self.jet_colour(idx, jet);
+        for shift in self.phi_shifts(ui.plot_bounds()) {
+            let centre = [y, shift + phi / PHI_SCALE];
+            self.draw_jet_circle(ui, centre, y_scale, jet_col);
         }
     }
 
-    fn draw_jet_circle(&self, ui: &mut egui_plot::PlotUi, centre: [f64; 2]) {
+    /// Label above each periodic image of a jet circle
+    fn draw_y_phi_jet_label(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        rank: usize,
+        jet: &PseudoJet,
+        y_scale: YScale,
+    ) {
+        let y_coord = y_scale.to_coord(self.settings.rapidity.of_jet(jet));
+        let mut phi: f64 = jet.phi().into();
+        if phi > PI {
+            phi -= 2.0 * PI;
+        }
+        let label = jet_label(rank, jet);
+        for shift in self.phi_shifts(ui.plot_bounds()) {
+            let phi_coord = shift + (phi + self.r_jet) / PHI_SCALE;
+            let pos = PlotPoint::new(y_coord, phi_coord);
+            let text = Text::new(pos, label.as_str())
+                .anchor(egui::Align2::CENTER_BOTTOM);
+            ui.text(text);
+        }
+    }
+
+    fn draw_y_phi_merge_candidates(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        particles: &[Particle],
+        y_scale: YScale,
+    ) {
+        let max_delta_r = self.merge_preview.max_delta_r.unwrap_or(self.r_jet);
+        let colour = self.merge_preview.colour;
+        for (i, p1) in particles.iter().enumerate() {
+            for p2 in &particles[(i + 1)..] {
+                if p1.delta_r(p2) >= max_delta_r {
+                    continue;
+                }
+                let y1 = y_scale.to_coord(self.settings.rapidity.of(p1));
+                let y2 = y_scale.to_coord(self.settings.rapidity.of(p2));
+                let start = [y1, p1.phi / PHI_SCALE];
+                let dphi = delta_phi(p2.phi, p1.phi);
+                let end = [y2, start[1] + dphi / PHI_SCALE];
+                let bounds = ui.plot_bounds();
+                for shift in self.phi_shifts(bounds) {
+                    let line = Line::new(vec![
+                        [start[0], start[1] + shift],
+                        [end[0], end[1] + shift],
+                    ])
+                    .color(colour);
+                    ui.line(line);
+                }
+            }
+        }
+    }
+
+    fn draw_y_phi_merge(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        merged: [PseudoJet; 2],
+        y_scale: YScale,
+    ) {
+        let [a, b] = merged;
+        let phi_a = delta_phi(a.phi().into(), 0.);
+        let y_a = y_scale.to_coord(self.settings.rapidity.of_jet(&a));
+        let y_b = y_scale.to_coord(self.settings.rapidity.of_jet(&b));
+        let start = [y_a, phi_a / PHI_SCALE];
+        let dphi = delta_phi(b.phi().into(), phi_a);
+        let end = [y_b, start[1] + dphi / PHI_SCALE];
+        for shift in self.phi_shifts(ui.plot_bounds()) {
+            let line = Line::new(vec![
+                [start[0], start[1] + shift],
+                [end[0], end[1] + shift],
+            ])
+            .color(self.settings.jets.to_opaque())
+            .width(2_f32)
+            .name("last merge");
+            ui.line(line);
+        }
+    }
+
+    /// Dashed line at the azimuthal angle of the missing transverse momentum
+    fn draw_y_phi_missing_pt(&self, ui: &mut egui_plot::PlotUi, phi: f64) {
+        let bounds = ui.plot_bounds();
+        let [y_min, _] = bounds.min();
+        let [y_max, _] = bounds.max();
+        let colour = ui.ctx().style().visuals.strong_text_color();
+        for shift in self.phi_shifts(bounds) {
+            let phi_coord = shift + phi / PHI_SCALE;
+            let line = Line::new(vec![[y_min, phi_coord], [y_max, phi_coord]])
+                .color(colour)
+                .width(2_f32)
+                .style(egui_plot::LineStyle::dashed_loose())
+                .name(MISSING_PT);
+            ui.line(line);
+        }
+    }
+
+    /// Hollow marker for an incoming particle at the edge of the y-φ plot
+    ///
+    /// Incoming particles usually move along the beam axis, so they are
+    /// drawn on the side their longitudinal momentum points to.
+    fn draw_y_phi_incoming(
+        &mut self,
+        ui: &mut egui_plot::PlotUi,
+        particle: &Particle,
+    ) {
+        let bounds = ui.plot_bounds();
+        let [y_min, _] = bounds.min();
+        let [y_max, _] = bounds.max();
+        let y_coord = if particle.p[3] < 0. { y_min } else { y_max };
+        let style = self.get_particle_style(particle.id);
+        let name = format!("{INCOMING} {}", particle.name());
+        for shift in self.phi_shifts(bounds) {
+            let centre = [y_coord, shift + particle.phi / PHI_SCALE];
+            let marker = Points::new(centre)
+                .color(style.colour)
+                .radius(style.size)
+                .shape(style.shape.into())
+                .filled(false)
+                .name(&name);
+            ui.points(marker);
+        }
+    }
+
+    fn draw_jet_circle(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        centre: [f64; 2],
+        y_scale: YScale,
+        jet_col: egui::Color32,
+    ) {
         let [y, phi] = centre;
-        let jet_col = self.settings.jets;
         let r = self.r_jet;
         let circle = Polygon::new(PlotPoints::from_parametric_callback(
-            |a| (y + r * a.sin(), phi + r * a.cos() / PHI_SCALE),
+            |a| {
+                let y_coord = y_scale.to_coord(y + r * a.sin());
+                (y_coord, phi + r * a.cos() / PHI_SCALE)
+            },
             0.0..(2. * PI),
             100,
         ));
 
-        let jet_circle = circle.width(0.).fill_color(jet_col).name("jet");
+        let jet_circle = circle.width(0_f32).fill_color(jet_col).name("jet");
         ui.polygon(jet_circle);
     }
 
@@ -559,6 +1899,7 @@ impl Plotter {
         &mut self,
         ui: &mut egui_plot::PlotUi,
         particle: &Particle,
+        pt_scale: PtScale,
     ) {
         let Particle { id, y, pt, .. } = particle;
         debug!(
@@ -566,42 +1907,358 @@ impl Plotter {
             id.id(),
             pt.log10()
         );
-        let centre = [*y, pt.log10()];
-        self.draw_particle_at(ui, *id, centre);
+        let centre =
+            [self.settings.rapidity.of(particle), pt_scale.to_coord(*pt)];
+        self.draw_particle_at(ui, particle, centre);
+    }
+
+    fn jet_colour(&self, idx: usize, jet: &PseudoJet) -> egui::Color32 {
+        match &self.highlight {
+            Some(highlight) if highlight.jet == *jet => {
+                let [r, g, b, _] = highlight.colour.to_srgba_unmultiplied();
+                let alpha = self.settings.jets.a();
+                egui::Color32::from_rgba_unmultiplied(r, g, b, alpha)
+            }
+            _ => self.settings.jet_colour(idx),
+        }
+    }
+
+    /// Label above the rectangle of a jet
+    fn draw_y_logpt_jet_label(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        rank: usize,
+        jet: &PseudoJet,
+        pt_scale: PtScale,
+    ) {
+        let pos = PlotPoint::new(
+            self.settings.rapidity.of_jet(jet),
+            pt_scale.to_coord(jet.pt().into()),
+        );
+        let text = Text::new(pos, jet_label(rank, jet))
+            .anchor(egui::Align2::CENTER_BOTTOM);
+        ui.text(text);
     }
 
-    fn draw_y_logpt_jet(&self, ui: &mut egui_plot::PlotUi, jet: &PseudoJet) {
+    fn draw_y_logpt_jet(
+        &self,
+        ui: &mut egui_plot::PlotUi,
+        idx: usize,
+        jet: &PseudoJet,
+        pt_scale: PtScale,
+    ) {
         debug!(
             "Drawing jet at (y, log(pt)) = ({}, {})",
             jet.rap(),
             jet.pt2().log10() / 2.
         );
-        let centre = (f64::from(jet.rap()), (jet.pt2().log10() / 2.).into());
-        let jet_col = self.settings.jets;
+        let centre = (
+            self.settings.rapidity.of_jet(jet),
+            pt_scale.to_coord(jet.pt().into()),
+        );
+        let jet_col = self.jet_colour(idx, jet);
         let pt_min = ui.plot_bounds().min()[1];
         let coord = [
             (centre.0 - self.r_jet, pt_min),
             (centre.0 + self.r_jet, centre.1),
         ];
         let rectangle = rectangle(coord)
-            .stroke(Stroke::new(0.0, jet_col))
+            .stroke(Stroke::new(0_f32, jet_col))
             .name("jet")
             .fill_color(jet_col);
         ui.polygon(rectangle);
     }
 }
 
-pub(crate) fn y_min_max(p: &[Particle]) -> [f64; 2] {
+/// Index of the jet whose circle in the y-φ plane contains `(y, phi)`
+///
+/// If several circles overlap, the jet with the closest axis is chosen.
+fn jet_at(
+    jet_axes: &[(f64, f64)],
+    y: f64,
+    phi: f64,
+    r_jet: f64,
+) -> Option<usize> {
+    jet_axes
+        .iter()
+        .enumerate()
+        .map(|(n, &(jet_y, jet_phi))| {
+            let dy = y - jet_y;
+            let dphi = delta_phi(phi, jet_phi);
+            (n, (dy * dy + dphi * dphi).sqrt())
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .filter(|&(_, dist)| dist < r_jet)
+        .map(|(n, _)| n)
+}
+
+/// Rank of each jet when ordered by decreasing transverse momentum
+fn pt_ranks(jets: &[PseudoJet]) -> Vec<usize> {
+    let mut order = Vec::from_iter(0..jets.len());
+    order.sort_by(|&a, &b| jets[b].pt2().cmp(&jets[a].pt2()));
+    let mut ranks = vec![0; jets.len()];
+    for (rank, idx) in order.into_iter().enumerate() {
+        ranks[idx] = rank;
+    }
+    ranks
+}
+
+/// Faint guides for the central rapidity and the beam directions
+///
+/// `y_zero` is the horizontal plot coordinate corresponding to zero
+/// rapidity.
+fn draw_beam_axis(ui: &mut egui_plot::PlotUi, y_zero: f64) {
+    let colour = ui.ctx().style().visuals.weak_text_color();
+    let line = VLine::new(y_zero)
+        .color(colour.gamma_multiply(0.5))
+        .style(egui_plot::LineStyle::dashed_loose());
+    ui.vline(line);
+    let bounds = ui.plot_bounds();
+    let [y_min, _] = bounds.min();
+    let [y_max, top] = bounds.max();
+    let left = Text::new(PlotPoint::new(y_min, top), "← beam")
+        .color(colour)
+        .anchor(egui::Align2::LEFT_TOP);
+    ui.text(left);
+    let right = Text::new(PlotPoint::new(y_max, top), "beam →")
+        .color(colour)
+        .anchor(egui::Align2::RIGHT_TOP);
+    ui.text(right);
+}
+
+/// Transverse projection of a charged track in a solenoid field
+///
+/// The track starts at the origin in the direction `phi` and bends with
+/// a radius of pT / (0.3 B q), with one plot unit corresponding to one
+/// metre. `field_charge` is the product B q of the field strength in
+/// tesla and the charge. The track ends at the radius `r_max` or after a
+/// full turn, whichever comes first.
+fn charged_track(
+    phi: f64,
+    pt: f64,
+    field_charge: f64,
+    r_max: f64,
+) -> Vec<(f64, f64)> {
+    const NUM_SEGMENTS: usize = 64;
+    let radius = pt / (0.3 * field_charge.abs());
+    // positive charges bend clockwise in a field along the beam axis
+    let sign = field_charge.signum();
+    let max_turn = if r_max < 2. * radius {
+        2. * (r_max / (2. * radius)).asin()
+    } else {
+        2. * PI
+    };
+    (0..=NUM_SEGMENTS)
+        .map(|t| {
+            let turn = max_turn * (t as f64) / (NUM_SEGMENTS as f64);
+            let dir = phi - sign * turn;
+            (
+                sign * radius * (phi.sin() - dir.sin()),
+                sign * radius * (dir.cos() - phi.cos()),
+            )
+        })
+        .collect()
+}
+
+fn jet_label(rank: usize, jet: &PseudoJet) -> String {
+    format!("jet {rank}\n{:.1} GeV", f64::from(jet.pt()))
+}
+
+/// Mapping from transverse momentum to the vertical plot coordinate
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum PtScale {
+    #[default]
+    Log,
+    Linear,
+}
+
+impl PtScale {
+    pub fn to_coord(self, pt: f64) -> f64 {
+        match self {
+            PtScale::Log => pt.log10(),
+            PtScale::Linear => pt,
+        }
+    }
+
+    /// Inverse of [`to_coord`](Self::to_coord)
+    pub fn inverse(self, coord: f64) -> f64 {
+        match self {
+            PtScale::Log => 10f64.powf(coord),
+            PtScale::Linear => coord,
+        }
+    }
+
+    fn axis_range(self, particles: &[Particle]) -> [f64; 2] {
+        let max_coord = particles
+            .iter()
+            .map(|p| self.to_coord(p.pt))
+            .min_by(|a, b| b.partial_cmp(a).unwrap())
+            .unwrap_or_default();
+        if self == PtScale::Linear {
+            return [0., 1.1 * max_coord];
+        }
+        let min_coord = particles
+            .iter()
+            .map(|p| self.to_coord(p.pt))
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+            .unwrap_or_default();
+        let min_coord = clamp_max(min_coord, max_coord - 1.0);
+        let range = max_coord - min_coord;
+        [min_coord - 0.1 * range, max_coord + 0.1 * range]
+    }
+}
+
+/// Longitudinal coordinate shown on the horizontal axis
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum Rapidity {
+    #[default]
+    #[strum(to_string = "y")]
+    Y,
+    #[strum(to_string = "η")]
+    Eta,
+}
+
+impl Rapidity {
+    pub fn of(self, particle: &Particle) -> f64 {
+        match self {
+            Rapidity::Y => particle.y,
+            Rapidity::Eta => particle.eta(),
+        }
+    }
+
+    pub fn of_jet(self, jet: &PseudoJet) -> f64 {
+        match self {
+            Rapidity::Y => jet.rap().into(),
+            Rapidity::Eta => f64::from(jet.pz() / jet.pt()).asinh(),
+        }
+    }
+
+    /// Symbol in LaTeX math mode
+    pub(crate) fn latex(self) -> &'static str {
+        match self {
+            Rapidity::Y => "y",
+            Rapidity::Eta => r"\eta",
+        }
+    }
+}
+
+/// Mapping from rapidity to the horizontal plot coordinate
+#[derive(
+    Display,
+    EnumIter,
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    PartialEq,
+    Eq,
+    Deserialize,
+    Serialize,
+)]
+pub enum YScale {
+    #[default]
+    Linear,
+    /// Maps the full real axis to a finite range
+    Compressed,
+}
+
+/// Rapidity coordinate corresponding to infinite rapidity
+/// on a compressed scale
+const Y_COMPRESSED_MAX: f64 = 5.;
+
+impl YScale {
+    pub fn to_coord(self, y: f64) -> f64 {
+        match self {
+            YScale::Linear => y,
+            YScale::Compressed => {
+                Y_COMPRESSED_MAX * (y / Y_COMPRESSED_MAX).tanh()
+            }
+        }
+    }
+
+    /// Inverse of [`to_coord`](Self::to_coord)
+    pub fn inverse(self, coord: f64) -> f64 {
+        match self {
+            YScale::Linear => coord,
+            YScale::Compressed => {
+                let c = (coord / Y_COMPRESSED_MAX).clamp(-1., 1.);
+                Y_COMPRESSED_MAX * c.atanh()
+            }
+        }
+    }
+
+    fn axis_range(self, [y_min, y_max]: [f64; 2]) -> [f64; 2] {
+        match self {
+            YScale::Linear => [y_min, y_max],
+            YScale::Compressed => [-Y_COMPRESSED_MAX, Y_COMPRESSED_MAX],
+        }
+    }
+}
+
+fn y_compressed_tick_label(
+    coord: f64,
+    _max_chars: usize,
+    _axis_range: &RangeInclusive<f64>,
+) -> String {
+    if coord >= Y_COMPRESSED_MAX {
+        "∞".to_owned()
+    } else if coord <= -Y_COMPRESSED_MAX {
+        "-∞".to_owned()
+    } else {
+        format!("{:.1}", YScale::Compressed.inverse(coord))
+    }
+}
+
+/// Offsets of the periodic images in the y-φ plot
+///
+/// At most `max_images` offsets are returned, even for huge plot bounds.
+fn phi_shifts(
+    bounds: PlotBounds,
+    max_images: usize,
+) -> impl Iterator<Item = f64> {
+    let phi_max = bounds.max()[1];
+    let max_range = 4. * max_images as f64;
+    let phi_min = f64::max(bounds.min()[1], phi_max - max_range);
+    let first = 4. * (phi_min / 4.).floor() - 4.;
+    (0..max_images)
+        .map(move |n| first + 4. * n as f64)
+        // coordinates of drawn objects are at least -4
+        .take_while(move |shift| shift - 4. < phi_max)
+}
+
+pub(crate) fn y_min_max(p: &[Particle], rapidity: Rapidity) -> [f64; 2] {
     let y_min = p
         .iter()
-        .map(|p| p.y)
+        .map(|p| rapidity.of(p))
         .min_by(|a, b| a.total_cmp(b))
         .unwrap_or_default();
     let y_min = if y_min < 0. { 1.1 * y_min } else { 0.9 * y_min };
     let y_min = f64::min(y_min, -4.5);
     let y_max = p
         .iter()
-        .map(|p| p.y)
+        .map(|p| rapidity.of(p))
         .max_by(|a, b| a.total_cmp(b))
         .unwrap_or_default();
     let y_max = if y_max < 0. { 0.9 * y_max } else { 1.1 * y_max };
@@ -609,11 +2266,42 @@ pub(crate) fn y_min_max(p: &[Particle]) -> [f64; 2] {
     [y_min, y_max]
 }
 
+/// Response to a click on a particle
+///
+/// Shift-clicks select a pair, and clicks with the command key (Ctrl on
+/// most platforms) add the particle to the selection.
+fn select(ui: &egui_plot::PlotUi, particle: Particle) -> PlotResponse {
+    let modifiers = ui.ctx().input(|i| i.modifiers);
+    if modifiers.shift {
+        PlotResponse::AddedToPair(particle)
+    } else {
+        PlotResponse::Selected {
+            particles: vec![particle],
+            extend: modifiers.command,
+        }
+    }
+}
+
 fn export_menu(ui: &mut Ui) -> Option<ExportFormat> {
     use ExportFormat::*;
     if ui.button("Export to asymptote").clicked() {
         ui.close_menu();
         Some(Asymptote)
+    } else if ui.button("Export to matplotlib").clicked() {
+        ui.close_menu();
+        Some(Matplotlib)
+    } else if ui.button("Export to PDF").clicked() {
+        ui.close_menu();
+        Some(Pdf)
+    } else if ui.button("Export to PNG").clicked() {
+        ui.close_menu();
+        Some(Png)
+    } else if ui.button("Export particles to CSV").clicked() {
+        ui.close_menu();
+        Some(Csv)
+    } else if ui.button("Export event to JSON").clicked() {
+        ui.close_menu();
+        Some(Json)
     } else {
         None
     }
@@ -635,16 +2323,62 @@ fn phi_tick_label(
 ) -> String {
     let c = clamp_phi_coord(coord);
     match c {
-        c if c == 2.0 => "π",
-        c if c == 1.0 => "π/2",
-        c if c == 0.0 => "0",
-        c if c == -1.0 => "-π/2",
-        c if c == -2.0 => "-π",
+        2.0 => "π",
+        1.0 => "π/2",
+        0.0 => "0",
+        -1.0 => "-π/2",
+        -2.0 => "-π",
         _ => "",
     }
     .to_string()
 }
 
+/// Tick labels for a fixed φ range, without mapping to (-π, π]
+fn unwrapped_phi_tick_label(
+    coord: f64,
+    _max_chars: usize,
+    _axis_range: &RangeInclusive<f64>,
+) -> String {
+    if coord != coord.round() {
+        return String::new();
+    }
+    // coordinates are in units of π/2
+    let k = coord as i64;
+    match (k, k % 2) {
+        (0, _) => "0".to_owned(),
+        (2, _) => "π".to_owned(),
+        (-2, _) => "-π".to_owned(),
+        (1, _) => "π/2".to_owned(),
+        (-1, _) => "-π/2".to_owned(),
+        (k, 0) => format!("{}π", k / 2),
+        (k, _) => format!("{k}π/2"),
+    }
+}
+
+/// Particle closest to a plot position according to `dist_sq`
+fn nearest_particle(
+    particles: &[Particle],
+    dist_sq: impl Fn(&Particle) -> f64,
+) -> Option<&Particle> {
+    particles
+        .iter()
+        .map(|p| (p, dist_sq(p)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(p, _)| p)
+}
+
+/// Name, four-momentum, and derived quantities for a tooltip
+fn particle_details(particle: &Particle) -> String {
+    let [e, px, py, pz] = particle.p;
+    format!(
+        "\n\n{}\nE = {e:.2}\npx = {px:.2}\npy = {py:.2}\npz = {pz:.2}\n\
+         m = {:.2}\npT = {:.2}",
+        particle.name(),
+        particle.mass(),
+        particle.pt,
+    )
+}
+
 fn clamp_phi_coord(coord: f64) -> f64 {
     let c = coord % 4.0;
     if c > 2.0 {
@@ -691,35 +2425,71 @@ fn fmt_superscript(mut i: i64) -> String {
     res.chars().rev().collect()
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum PlotResponse {
     Export {
         kind: PlotKind,
         format: ExportFormat,
     },
-    Selected(Particle),
+    /// Particles whose species are styled together
+    ///
+    /// With `extend`, they are toggled in the existing selection instead
+    /// of replacing it.
+    Selected {
+        particles: Vec<Particle>,
+        extend: bool,
+    },
+    /// Particle added to the pair for the invariant mass readout
+    AddedToPair(Particle),
+    SelectedJet(PseudoJet),
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Display, EnumIter)]
 pub enum PlotKind {
+    #[strum(to_string = "y-φ")]
     YPhi,
+    #[strum(to_string = "y-log(pT)")]
     YLogPt,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(
+    Copy, Clone, Debug, Display, EnumIter, Eq, PartialEq, Ord, PartialOrd, Hash,
+)]
 pub enum ExportFormat {
     Asymptote,
+    Csv,
+    Json,
+    Matplotlib,
+    Pdf,
+    Png,
 }
 
 impl ExportFormat {
     pub(crate) fn suffix(&self) -> &'static str {
         match self {
             ExportFormat::Asymptote => "asy",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Matplotlib => "py",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Png => "png",
         }
     }
 }
 
-fn to_plotters_col(col: egui::Color32) -> RGBAColor {
+pub(crate) fn to_plotters_col(col: egui::Color32) -> RGBAColor {
     let (r, g, b, a) = col.to_tuple();
     RGBAColor(r, g, b, (a as f64) / (u8::MAX as f64))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plot_3d_without_area() {
+        let mut plotter = Plotter::default();
+        let res = plotter.plot_3d(&Event::default(), &[], &mut [], [0, 0]);
+        assert!(res.is_err());
+    }
+}
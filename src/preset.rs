@@ -0,0 +1,47 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::plotter::{Plotter, Settings, Settings3D};
+
+/// Named collection of all plot styles, e.g. for papers or screens
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub(crate) struct Preset {
+    pub(crate) settings: Settings,
+    #[serde(default)]
+    pub(crate) settings_3d: Settings3D,
+}
+
+impl Preset {
+    pub(crate) fn from_plotter(plotter: &Plotter) -> Self {
+        Self {
+            settings: plotter.settings.clone(),
+            settings_3d: plotter.settings_3d.clone(),
+        }
+    }
+
+    /// Replace the styles of `plotter` by the ones in this preset
+    pub(crate) fn apply(self, plotter: &mut Plotter) {
+        plotter.settings = self.settings;
+        plotter.settings_3d = self.settings_3d;
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        let out = File::create(path)
+            .with_context(|| format!("Failed to open {path:?}"))?;
+        serde_json::to_writer_pretty(BufWriter::new(out), self)
+            .with_context(|| format!("Failed to write preset to {path:?}"))
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open {path:?}"))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to read preset from {path:?}"))
+    }
+}
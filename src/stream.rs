@@ -0,0 +1,135 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{auto_decompress::is_compressed, event::Event, hepmc3};
+
+/// Number of events kept in memory while streaming
+const WINDOW_SIZE: usize = 200;
+/// Minimum number of events kept before and after the current one
+const WINDOW_MARGIN: usize = 20;
+
+/// Check whether events can be read from a file on demand
+///
+/// This requires random access, so only uncompressed HepMC3 files are
+/// supported.
+pub(crate) fn is_streamable(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut start = Vec::new();
+    if file.take(8).read_to_end(&mut start).is_err() {
+        return false;
+    }
+    !is_compressed(&start) && hepmc3::is_hepmc3(path)
+}
+
+/// Byte offsets of all events in a HepMC3 file
+#[derive(Clone, Debug)]
+pub(crate) struct EventIndex {
+    path: PathBuf,
+    offsets: Vec<u64>,
+}
+
+impl EventIndex {
+    /// Scan the file once, recording where each event starts
+    pub(crate) fn build(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open {path:?}"))?;
+        let mut input = BufReader::new(file);
+        let mut offsets = Vec::new();
+        let mut pos = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let len = input.read_until(b'\n', &mut line)?;
+            if len == 0 {
+                break;
+            }
+            if line.starts_with(b"E ") {
+                offsets.push(pos);
+            }
+            pos += len as u64;
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            offsets,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Parse up to `count` events, starting with the one at index `start`
+    fn read(&self, start: usize, count: usize) -> Result<Vec<Event>> {
+        let Some(&offset) = self.offsets.get(start) else {
+            return Ok(Vec::new());
+        };
+        let mut file = File::open(&self.path)
+            .with_context(|| format!("Failed to open {:?}", self.path))?;
+        file.seek(SeekFrom::Start(offset))?;
+        hepmc3::Reader::from_input(Box::new(BufReader::new(file)))
+            .take(count)
+            .collect()
+    }
+}
+
+/// Window of events read on demand from an indexed file
+#[derive(Debug)]
+pub(crate) struct EventStream {
+    index: EventIndex,
+    /// Index of the first event in the window
+    start: usize,
+}
+
+impl EventStream {
+    pub(crate) fn new(index: EventIndex) -> Self {
+        Self { index, start: 0 }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        self.index.path()
+    }
+
+    /// Total number of events in the file
+    pub(crate) fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Index of the first event in the current window
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Read the window of events around the event at index `idx`
+    ///
+    /// Returns the events together with the index of the requested event
+    /// inside the window.
+    pub(crate) fn seek(&mut self, idx: usize) -> Result<(Vec<Event>, usize)> {
+        let idx = idx.min(self.len().saturating_sub(1));
+        let start = idx
+            .saturating_sub(WINDOW_SIZE / 2)
+            .min(self.len().saturating_sub(WINDOW_SIZE));
+        let events = self.index.read(start, WINDOW_SIZE)?;
+        self.start = start;
+        Ok((events, idx - start))
+    }
+
+    /// Check whether the window has to move to keep the event at index
+    /// `idx` inside the window away from its edges
+    pub(crate) fn needs_seek(&self, idx: usize, window_len: usize) -> bool {
+        let near_start = idx < WINDOW_MARGIN && self.start > 0;
+        let near_end = idx + WINDOW_MARGIN >= window_len
+            && self.start + window_len < self.len();
+        near_start || near_end
+    }
+}
@@ -1,15 +1,28 @@
+use std::collections::HashMap;
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use egui::{Context, DragValue, Pos2};
+use egui_plot::{Bar, BarChart};
 use jetty::PseudoJet;
 use lazy_static::lazy_static;
+use log::error;
 use particle_id::ParticleID;
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 
+use crate::app::{rgb_to_rgba, BYTES_PER_RGBA_PIXEL};
+use crate::clustering::{n_subjettiness, ClusterSettings, JetDefinition};
 use crate::event::Event;
-use crate::plotter::{self, ExportFormat, PlotKind, PlotResponse, Plotter};
+use crate::export::{ExportOptions, FigureSize};
+use crate::histogram::{event_weight, Binning, Histogram, SpeciesList};
+use crate::observables::{sphericity, thrust, EventCondition, EventPredicate};
+use crate::particle::{delta_phi, particle_name, Particle};
+use crate::plotter::{
+    self, ExportFormat, JetHighlight, ParticleStyle, PlotKind, PlotResponse,
+    Plotter, PtScale, Rapidity, YScale,
+};
 
 lazy_static! {
     static ref FONT_NAMES: Vec<String> = {
@@ -27,6 +40,11 @@ pub(crate) struct YLogPtWin {
     pub(crate) is_open: bool,
     y: Range<f64>,
     logpt: Range<f64>,
+    #[serde(default)]
+    pub(crate) pt_scale: PtScale,
+    /// Use `y` and `logpt` instead of data-driven axis ranges
+    #[serde(default)]
+    fixed_range: bool,
 }
 
 impl Default for YLogPtWin {
@@ -35,6 +53,8 @@ impl Default for YLogPtWin {
             is_open: true,
             y: -5.0..5.0,
             logpt: -2.0..3.0,
+            pt_scale: PtScale::default(),
+            fixed_range: false,
         }
     }
 }
@@ -55,15 +75,183 @@ impl YLogPtWin {
             .title_bar(true)
             .min_width(100.)
             .min_height(100.)
-            .show(ctx, |ui| plotter.plot_y_logpt(ui, event, jets))
+            .show(ctx, |ui| {
+                rapidity_choice(
+                    ui,
+                    "y logpt rapidity",
+                    &mut plotter.settings.rapidity,
+                );
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("pt scale")
+                        .selected_text(self.pt_scale.to_string())
+                        .show_ui(ui, |ui| {
+                            for scale in PtScale::iter() {
+                                ui.selectable_value(
+                                    &mut self.pt_scale,
+                                    scale,
+                                    scale.to_string(),
+                                );
+                            }
+                        });
+                    ui.label("Transverse momentum scale");
+                });
+                let changed = ui.collapsing("Axis ranges", |ui| {
+                    let mut changed =
+                        auto_range_choice(ui, &mut self.fixed_range);
+                    ui.add_enabled_ui(self.fixed_range, |ui| {
+                        changed |= range_edit(ui, "y", &mut self.y);
+                        changed |= range_edit(ui, "log₁₀(pT)", &mut self.logpt);
+                    });
+                    changed
+                });
+                let changed = changed.body_returned.unwrap_or_default();
+                let fit = fit_to_data_button(ui) | changed;
+                let range = self
+                    .fixed_range
+                    .then(|| (self.y.clone(), self.logpt.clone()));
+                // only reset this plot, not all plots drawn in this frame
+                let reset_all = plotter.reset_view;
+                plotter.reset_view |= fit;
+                let response =
+                    plotter.plot_y_logpt(ui, event, jets, self.pt_scale, range);
+                plotter.reset_view = reset_all;
+                response
+            })
             .and_then(|e| e.inner.flatten())
     }
 }
 
+/// Checkbox for data-driven axis ranges, returns whether it was changed
+fn auto_range_choice(ui: &mut egui::Ui, fixed_range: &mut bool) -> bool {
+    let mut auto = !*fixed_range;
+    let changed = ui.checkbox(&mut auto, "Automatic").changed();
+    *fixed_range = !auto;
+    changed
+}
+
+fn range_edit(ui: &mut egui::Ui, label: &str, range: &mut Range<f64>) -> bool {
+    let changed = ui
+        .horizontal(|ui| {
+            let start = ui.add(DragValue::new(&mut range.start).speed(0.1));
+            ui.label("–");
+            let end = ui.add(DragValue::new(&mut range.end).speed(0.1));
+            ui.label(label);
+            start.changed() || end.changed()
+        })
+        .inner;
+    if range.start > range.end {
+        std::mem::swap(&mut range.start, &mut range.end);
+    }
+    changed
+}
+
+/// Number of bins and their range, returns whether anything changed
+fn binning_edit(ui: &mut egui::Ui, label: &str, binning: &mut Binning) -> bool {
+    let mut range = binning.min..binning.max;
+    let mut changed = range_edit(ui, label, &mut range);
+    [binning.min, binning.max] = [range.start, range.end];
+    ui.horizontal(|ui| {
+        let nbins = DragValue::new(&mut binning.nbins).clamp_range(1..=1000);
+        changed |= ui.add(nbins).changed();
+        ui.label("Bins");
+    });
+    changed
+}
+
+/// Checkbox for filling histograms with event weights instead of unit
+/// entries, returns whether it was changed
+fn weighting_choice(ui: &mut egui::Ui, weighted: &mut bool) -> bool {
+    ui.checkbox(weighted, "Fill with event weights").changed()
+}
+
+/// Axis label for the number of histogram entries
+fn entries_label(weighted: bool) -> &'static str {
+    if weighted {
+        "weighted number of particles"
+    } else {
+        "number of particles"
+    }
+}
+
+/// Sum of weights, sum of squared weights, and effective number of entries
+fn weight_summary(hist: &Histogram) -> String {
+    format!(
+        "Σw = {:.4e}, Σw² = {:.4e}, (Σw)²/Σw² = {:.1}",
+        hist.sum_weights(),
+        hist.sum_weights_sq(),
+        hist.effective_entries()
+    )
+}
+
+fn default_weighted() -> bool {
+    true
+}
+
+/// Choice of a particle species present in the sample, `None` for all
+fn species_choice(
+    ui: &mut egui::Ui,
+    id: &str,
+    species: &mut Option<ParticleID>,
+    choices: &[ParticleID],
+) {
+    let name = |species: Option<ParticleID>| match species {
+        Some(id) => particle_name(id).into_owned(),
+        None => "all".to_owned(),
+    };
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(name(*species))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(species, None, name(None));
+                for &choice in choices {
+                    let text = name(Some(choice));
+                    ui.selectable_value(species, Some(choice), text);
+                }
+            });
+        ui.label("Particles");
+    });
+}
+
+/// Button to undo zooming and panning, returns whether it was clicked
+fn fit_to_data_button(ui: &mut egui::Ui) -> bool {
+    ui.button("Fit to data")
+        .on_hover_text("Reset the view, also by double-clicking the plot")
+        .clicked()
+}
+
+fn rapidity_choice(ui: &mut egui::Ui, id: &str, rapidity: &mut Rapidity) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(rapidity.to_string())
+            .show_ui(ui, |ui| {
+                for choice in Rapidity::iter() {
+                    ui.selectable_value(rapidity, choice, choice.to_string());
+                }
+            });
+        ui.label("Horizontal axis");
+    });
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct YPhiWin {
     pub(crate) is_open: bool,
     y: Range<f64>,
+    #[serde(default)]
+    y_scale: YScale,
+    #[serde(default)]
+    show_missing_pt: bool,
+    /// Use `y` instead of the data-driven rapidity range
+    #[serde(default)]
+    fixed_range: bool,
+    /// Show `phi_periods` periods of φ instead of filling the plot
+    #[serde(default)]
+    pub(crate) fixed_phi_range: bool,
+    #[serde(default = "default_phi_periods")]
+    phi_periods: f64,
+}
+
+fn default_phi_periods() -> f64 {
+    1.
 }
 
 impl Default for YPhiWin {
@@ -71,6 +259,11 @@ impl Default for YPhiWin {
         Self {
             is_open: true,
             y: -5.0..5.0,
+            y_scale: YScale::default(),
+            show_missing_pt: false,
+            fixed_range: false,
+            fixed_phi_range: false,
+            phi_periods: default_phi_periods(),
         }
     }
 }
@@ -91,16 +284,1053 @@ impl YPhiWin {
             .title_bar(true)
             .min_width(100.)
             .min_height(100.)
-            .show(ctx, |ui| plotter.plot_y_phi(ui, event, jets))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source("y scale")
+                        .selected_text(self.y_scale.to_string())
+                        .show_ui(ui, |ui| {
+                            for scale in YScale::iter() {
+                                ui.selectable_value(
+                                    &mut self.y_scale,
+                                    scale,
+                                    scale.to_string(),
+                                );
+                            }
+                        });
+                    ui.label("Rapidity scale");
+                });
+                rapidity_choice(
+                    ui,
+                    "y phi rapidity",
+                    &mut plotter.settings.rapidity,
+                );
+                ui.checkbox(
+                    &mut self.show_missing_pt,
+                    "Show missing transverse momentum",
+                );
+                let changed = ui.collapsing("Axis range", |ui| {
+                    let mut changed =
+                        auto_range_choice(ui, &mut self.fixed_range);
+                    ui.add_enabled_ui(self.fixed_range, |ui| {
+                        changed |= range_edit(ui, "y", &mut self.y);
+                    });
+                    changed |= ui
+                        .checkbox(&mut self.fixed_phi_range, "Fixed φ range")
+                        .changed();
+                    ui.add_enabled_ui(self.fixed_phi_range, |ui| {
+                        ui.horizontal(|ui| {
+                            let periods = DragValue::new(&mut self.phi_periods)
+                                .speed(0.05)
+                                .clamp_range(0.5..=8.0);
+                            changed |= ui.add(periods).changed();
+                            ui.label("Periods of φ");
+                        });
+                    });
+                    changed
+                });
+                let changed = changed.body_returned.unwrap_or_default();
+                let fit = fit_to_data_button(ui) | changed;
+                // only reset this plot, not all plots drawn in this frame
+                let reset_all = plotter.reset_view;
+                plotter.reset_view |= fit;
+                let response = plotter.plot_y_phi(
+                    ui,
+                    event,
+                    jets,
+                    self.y_scale,
+                    self.show_missing_pt,
+                    self.fixed_range.then(|| self.y.clone()),
+                    self.fixed_phi_range.then_some(self.phi_periods),
+                );
+                plotter.reset_view = reset_all;
+                response
+            })
             .and_then(|e| e.inner.flatten())
     }
 }
 
-// TODO: choice for jets
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct EventInfoWin {
+    pub(crate) is_open: bool,
+}
+
+impl EventInfoWin {
+    pub(crate) fn show(&mut self, ctx: &egui::Context, event: &Event) {
+        let info = &event.info;
+        let fmt = |val: Option<f64>| {
+            val.map(|v| format!("{v:.4}"))
+                .unwrap_or_else(|| "N/A".to_owned())
+        };
+        egui::Window::new("Event information")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("event info grid").show(ui, |ui| {
+                    ui.label("Event id");
+                    if let Some(id) = info.id {
+                        ui.label(id.to_string());
+                    } else {
+                        ui.label("N/A");
+                    }
+                    ui.end_row();
+                    ui.label("Weight");
+                    ui.label(fmt(info.weight()));
+                    ui.end_row();
+                    let alternative = info.alternative_weights();
+                    if !alternative.is_empty() {
+                        ui.label("Alternative weights");
+                        ui.label(alternative.len().to_string());
+                        ui.end_row();
+                    }
+                    ui.label("Renormalisation scale");
+                    ui.label(fmt(info.mu_r));
+                    ui.end_row();
+                    ui.label("Factorisation scale");
+                    ui.label(fmt(info.mu_f));
+                    ui.end_row();
+                    ui.label("Parton shower scale");
+                    ui.label(fmt(info.mu_ps));
+                    ui.end_row();
+                    ui.label("αₛ");
+                    ui.label(fmt(info.alpha_s));
+                    ui.end_row();
+                    ui.label("α");
+                    ui.label(fmt(info.alpha));
+                    ui.end_row();
+                });
+            });
+    }
+}
+
+/// Condition for the "Find next" event navigation
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct FindEventWin {
+    pub(crate) is_open: bool,
+    pub(crate) predicate: EventPredicate,
+}
+
+impl FindEventWin {
+    /// Show the window, returns whether a search was requested
+    pub(crate) fn show(&mut self, ctx: &egui::Context) -> bool {
+        let mut find = false;
+        let predicate = &mut self.predicate;
+        egui::Window::new("Find event")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Find events with");
+                    egui::ComboBox::from_id_source("Event condition")
+                        .selected_text(predicate.condition.to_string())
+                        .show_ui(ui, |ui| {
+                            for condition in EventCondition::iter() {
+                                ui.selectable_value(
+                                    &mut predicate.condition,
+                                    condition,
+                                    condition.to_string(),
+                                );
+                            }
+                        });
+                });
+                ui.horizontal(|ui| match predicate.condition {
+                    EventCondition::MinMultiplicity => {
+                        ui.add(DragValue::new(&mut predicate.multiplicity));
+                        ui.label("Minimum number of particles");
+                    }
+                    EventCondition::ParticleAbovePt => {
+                        ui.add(
+                            DragValue::new(&mut predicate.pt)
+                                .clamp_range(0.0..=f64::MAX),
+                        );
+                        ui.label("Transverse momentum");
+                    }
+                });
+                find = ui.button("Find next").clicked();
+            });
+        find
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct EventSummaryWin {
+    pub(crate) is_open: bool,
+}
+
+impl EventSummaryWin {
+    pub(crate) fn show(&mut self, ctx: &egui::Context, event: &Event) {
+        let [e, px, py, pz] = event.visible_momentum();
+        let mass = (e * e - px * px - py * py - pz * pz).max(0.).sqrt();
+        let [missing_px, missing_py] = event.missing_pt();
+        egui::Window::new("Event summary")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("event summary grid").show(ui, |ui| {
+                    ui.label("Weight");
+                    if let Some(weight) = event.info.weight() {
+                        ui.label(format!("{weight:.4e}"));
+                    } else {
+                        ui.label("N/A");
+                    }
+                    ui.end_row();
+                    ui.label("Outgoing particles");
+                    ui.label(event.out.len().to_string());
+                    ui.end_row();
+                    ui.label("Scalar sum of pT");
+                    ui.label(format!("{:.2} GeV", event.ht()));
+                    ui.end_row();
+                    ui.label("Visible energy");
+                    ui.label(format!("{e:.2} GeV"));
+                    ui.end_row();
+                    ui.label("Visible invariant mass");
+                    ui.label(format!("{mass:.2} GeV"));
+                    ui.end_row();
+                    ui.label("Missing transverse momentum");
+                    ui.label(format!(
+                        "{:.2} GeV",
+                        missing_px.hypot(missing_py)
+                    ));
+                    ui.end_row();
+                    ui.label("Thrust");
+                    ui.label(format!("{:.4}", thrust(event)));
+                    ui.end_row();
+                    ui.label("Sphericity");
+                    ui.label(format!("{:.4}", sphericity(event)));
+                    ui.end_row();
+                });
+            });
+    }
+}
+
+/// Transverse momentum spectrum of all loaded events
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PtSpectrumWin {
+    pub(crate) is_open: bool,
+    /// Only include particles of this species
+    species: Option<ParticleID>,
+    binning: Binning,
+    /// Fill with event weights instead of unit entries
+    #[serde(default = "default_weighted")]
+    weighted: bool,
+    #[serde(skip)]
+    species_list: SpeciesList,
+    /// Histogram with the number of events, the species, and the weighting
+    /// it was filled for
+    #[serde(skip)]
+    cache: Option<(usize, Option<ParticleID>, bool, Histogram)>,
+}
+
+impl Default for PtSpectrumWin {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            species: None,
+            binning: Binning {
+                nbins: 50,
+                min: 0.,
+                max: 200.,
+            },
+            weighted: default_weighted(),
+            species_list: SpeciesList::default(),
+            cache: None,
+        }
+    }
+}
+
+impl PtSpectrumWin {
+    /// Forget everything derived from the previously shown events
+    pub(crate) fn clear_cache(&mut self) {
+        self.species_list = SpeciesList::default();
+        self.cache = None;
+    }
+
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        events: &[Event],
+        settings: &plotter::Settings,
+    ) {
+        if !self.is_open {
+            return;
+        }
+        self.update_histogram(events);
+        egui::Window::new("Transverse momentum spectrum")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                let choices = self.species_list.get(events);
+                species_choice(ui, "pT species", &mut self.species, choices);
+                binning_edit(ui, "pT [GeV]", &mut self.binning);
+                weighting_choice(ui, &mut self.weighted);
+                let Some((_, species, weighted, hist)) = &self.cache else {
+                    return;
+                };
+                let colour = match species {
+                    Some(id) => settings.particle_style(*id).colour,
+                    None => ui.visuals().strong_text_color(),
+                };
+                let width = hist.binning().width();
+                let bars = Vec::from_iter(
+                    hist.bins().map(|(x, n)| Bar::new(x, n).width(width)),
+                );
+                ui.label(weight_summary(hist));
+                egui_plot::Plot::new("pT spectrum plot")
+                    .x_axis_label("pT [GeV]")
+                    .y_axis_label(entries_label(*weighted))
+                    .show(ui, |ui| {
+                        ui.bar_chart(BarChart::new(bars).color(colour))
+                    });
+            });
+    }
+
+    fn update_histogram(&mut self, events: &[Event]) {
+        if let Some((nevents, species, weighted, hist)) = &self.cache {
+            if *nevents == events.len()
+                && *species == self.species
+                && *weighted == self.weighted
+                && hist.binning() == self.binning
+            {
+                return;
+            }
+        }
+        let mut hist = Histogram::new(self.binning);
+        for event in events {
+            let weight = event_weight(event, self.weighted);
+            let particles = event.out.iter().filter(|p| {
+                self.species.is_none() || self.species == Some(p.id)
+            });
+            for particle in particles {
+                hist.fill(particle.pt, weight);
+            }
+        }
+        self.cache = Some((events.len(), self.species, self.weighted, hist));
+    }
+}
+
+/// Rapidity distributions of selected species in all loaded events
 #[derive(Deserialize, Serialize)]
+pub(crate) struct RapidityDistributionWin {
+    pub(crate) is_open: bool,
+    /// Species with an overlaid histogram each
+    species: Vec<ParticleID>,
+    binning: Binning,
+    /// Fill with event weights instead of unit entries
+    #[serde(default = "default_weighted")]
+    weighted: bool,
+    #[serde(skip)]
+    species_list: SpeciesList,
+    /// Histograms with the number of events they were filled from and
+    /// whether they are weighted
+    #[serde(skip)]
+    cache: Option<(usize, bool, SpeciesHistograms)>,
+}
+
+type SpeciesHistograms = Vec<(ParticleID, Histogram)>;
+
+impl Default for RapidityDistributionWin {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            species: Vec::new(),
+            binning: Binning {
+                nbins: 40,
+                min: -5.,
+                max: 5.,
+            },
+            weighted: default_weighted(),
+            species_list: SpeciesList::default(),
+            cache: None,
+        }
+    }
+}
+
+impl RapidityDistributionWin {
+    /// Forget everything derived from the previously shown events
+    pub(crate) fn clear_cache(&mut self) {
+        self.species_list = SpeciesList::default();
+        self.cache = None;
+    }
+
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        events: &[Event],
+        settings: &plotter::Settings,
+    ) {
+        if !self.is_open {
+            return;
+        }
+        self.update_histograms(events);
+        egui::Window::new("Rapidity distribution")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for &id in self.species_list.get(events) {
+                        let mut shown = self.species.contains(&id);
+                        let name = particle_name(id).into_owned();
+                        if ui.checkbox(&mut shown, name).changed() {
+                            if shown {
+                                self.species.push(id);
+                            } else {
+                                self.species.retain(|s| *s != id);
+                            }
+                        }
+                    }
+                });
+                binning_edit(ui, "y", &mut self.binning);
+                weighting_choice(ui, &mut self.weighted);
+                let Some((_, weighted, hists)) = &self.cache else {
+                    return;
+                };
+                for (id, hist) in hists {
+                    let name = particle_name(*id);
+                    ui.label(format!("{name}: {}", weight_summary(hist)));
+                }
+                egui_plot::Plot::new("rapidity distribution plot")
+                    .x_axis_label("y")
+                    .y_axis_label(entries_label(*weighted))
+                    .legend(egui_plot::Legend::default())
+                    .show(ui, |ui| {
+                        for (id, hist) in hists {
+                            let colour = settings.particle_style(*id).colour;
+                            let line = egui_plot::Line::new(hist.outline())
+                                .color(colour)
+                                .name(particle_name(*id));
+                            ui.line(line);
+                        }
+                    });
+            });
+    }
+
+    fn update_histograms(&mut self, events: &[Event]) {
+        if let Some((nevents, weighted, hists)) = &self.cache {
+            let unchanged = *nevents == events.len()
+                && *weighted == self.weighted
+                && hists.iter().map(|(id, _)| id).eq(&self.species)
+                && hists.iter().all(|(_, h)| h.binning() == self.binning);
+            if unchanged {
+                return;
+            }
+        }
+        let mut hists = Vec::from_iter(
+            self.species
+                .iter()
+                .map(|&id| (id, Histogram::new(self.binning))),
+        );
+        for event in events {
+            let weight = event_weight(event, self.weighted);
+            for particle in &event.out {
+                let hist = hists.iter_mut().find(|(id, _)| *id == particle.id);
+                if let Some((_, hist)) = hist {
+                    hist.fill(particle.y, weight);
+                }
+            }
+        }
+        self.cache = Some((events.len(), self.weighted, hists));
+    }
+}
+
+/// Summary of the conventions used for plotting
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ConventionsWin {
+    pub(crate) is_open: bool,
+}
+
+impl ConventionsWin {
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        clustering: &ClusterSettings,
+        plotter: &Plotter,
+    ) {
+        egui::Window::new("Conventions")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("conventions grid").show(ui, |ui| {
+                    ui.label("Longitudinal coordinate");
+                    ui.label("rapidity y = ½ ln[(E + pz)/(E - pz)]");
+                    ui.end_row();
+                    ui.label("Azimuthal angle");
+                    ui.label("φ ∈ (-π, π]");
+                    ui.end_row();
+                    ui.label("Units");
+                    ui.label("GeV");
+                    ui.end_row();
+                    ui.label("Shown particles");
+                    ui.label("outgoing");
+                    ui.end_row();
+                    ui.label("Transverse momentum cuts");
+                    if plotter.settings.min_pt.is_empty() {
+                        ui.label("none");
+                    } else {
+                        let ncuts = plotter.settings.min_pt.len();
+                        ui.label(format!("{ncuts} particle species"));
+                    }
+                    ui.end_row();
+                    ui.label("Jets");
+                    if clustering.clustering_enabled {
+                        let jet_def = &clustering.jet_def;
+                        let mut desc = format!(
+                            "{}, R = {}, pT > {} GeV",
+                            jet_def.algorithm, jet_def.radius, jet_def.min_pt
+                        );
+                        if let Some(max_rap) = jet_def.max_rap {
+                            desc += &format!(", |y| < {max_rap}");
+                        }
+                        if let Some(grooming) = jet_def.grooming {
+                            desc += &format!(
+                                ", {grooming} with {}",
+                                grooming.parameters()
+                            );
+                        }
+                        ui.label(desc);
+                    } else {
+                        ui.label("disabled");
+                    }
+                    ui.end_row();
+                    ui.label("Jet constituents");
+                    ui.label("partons and hadrons");
+                    ui.end_row();
+                });
+            });
+    }
+}
+
+/// A paginated grid of small y-φ plots
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ThumbnailWin {
+    pub(crate) is_open: bool,
+    #[serde(skip)]
+    page: usize,
+    #[serde(skip)]
+    textures: HashMap<usize, egui::TextureHandle>,
+    // settings and number of events the cached textures were drawn for
+    #[serde(skip)]
+    cache_key: Option<(plotter::Settings, usize)>,
+}
+
+const THUMBNAIL_SIZE: [usize; 2] = [160, 120];
+const THUMBNAILS_PER_ROW: usize = 4;
+const THUMBNAILS_PER_PAGE: usize = 4 * THUMBNAILS_PER_ROW;
+
+impl ThumbnailWin {
+    /// Forget everything derived from the previously shown events
+    pub(crate) fn clear_cache(&mut self) {
+        self.textures.clear();
+        self.cache_key = None;
+    }
+
+    /// Returns the index of the event for a clicked thumbnail
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        plotter: &mut Plotter,
+        events: &[Event],
+    ) -> Option<usize> {
+        if !self.is_open {
+            return None;
+        }
+        let cache_key = Some((plotter.settings.clone(), events.len()));
+        if cache_key != self.cache_key {
+            self.textures.clear();
+            self.cache_key = cache_key;
+        }
+        let npages = events.len().div_ceil(THUMBNAILS_PER_PAGE);
+        self.page = std::cmp::min(self.page, npages.saturating_sub(1));
+
+        let mut selected = None;
+        let mut is_open = self.is_open;
+        egui::Window::new("Event overview")
+            .open(&mut is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let back =
+                        ui.add_enabled(self.page > 0, egui::Button::new("⇦"));
+                    if back.clicked() {
+                        self.page -= 1;
+                    }
+                    ui.label(format!(
+                        "Page {}/{}",
+                        self.page + 1,
+                        npages.max(1)
+                    ));
+                    let can_forward = self.page + 1 < npages;
+                    let forward =
+                        ui.add_enabled(can_forward, egui::Button::new("⇨"));
+                    if forward.clicked() {
+                        self.page += 1;
+                    }
+                });
+                let first = self.page * THUMBNAILS_PER_PAGE;
+                let last =
+                    std::cmp::min(first + THUMBNAILS_PER_PAGE, events.len());
+                egui::Grid::new("thumbnail grid").show(ui, |ui| {
+                    for idx in first..last {
+                        let texture = self.texture(ctx, plotter, events, idx);
+                        ui.vertical(|ui| {
+                            let img =
+                                egui::load::SizedTexture::from_handle(texture);
+                            let img = egui::Image::from_texture(img)
+                                .sense(egui::Sense::click());
+                            if ui.add(img).clicked() {
+                                selected = Some(idx);
+                            }
+                            ui.label(format!("Event {}", idx + 1));
+                        });
+                        if (idx + 1 - first).is_multiple_of(THUMBNAILS_PER_ROW)
+                        {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        self.is_open = is_open;
+        selected
+    }
+
+    fn texture(
+        &mut self,
+        ctx: &Context,
+        plotter: &mut Plotter,
+        events: &[Event],
+        idx: usize,
+    ) -> &egui::TextureHandle {
+        self.textures.entry(idx).or_insert_with(|| {
+            let [width, height] = THUMBNAIL_SIZE;
+            let mut img = vec![0u8; width * height * BYTES_PER_RGBA_PIXEL];
+            if let Err(err) = plotter.plot_y_phi_thumbnail(
+                &events[idx],
+                &mut img,
+                THUMBNAIL_SIZE,
+            ) {
+                error!("Failed to draw thumbnail for event {idx}: {err}");
+            }
+            rgb_to_rgba(&mut img);
+            let img =
+                egui::ColorImage::from_rgba_premultiplied(THUMBNAIL_SIZE, &img);
+            ctx.load_texture(
+                format!("thumbnail {idx}"),
+                img,
+                egui::TextureOptions::default(),
+            )
+        })
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct DisplayCutsWin {
+    pub(crate) is_open: bool,
+}
+
+impl DisplayCutsWin {
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        cuts: &mut plotter::DisplayCuts,
+    ) {
+        egui::Window::new("Display cuts")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(&mut cuts.min_pt)
+                            .clamp_range(0.0..=f64::MAX),
+                    );
+                    ui.label("Minimum transverse momentum");
+                });
+                ui.horizontal(|ui| {
+                    let mut y_cut = cuts.max_abs_y.is_some();
+                    ui.checkbox(&mut y_cut, "");
+                    if y_cut {
+                        let max_abs_y = cuts.max_abs_y.get_or_insert(2.5);
+                        ui.add(
+                            DragValue::new(max_abs_y)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f64::INFINITY),
+                        );
+                    } else {
+                        cuts.max_abs_y = None;
+                    }
+                    ui.label("Maximum absolute rapidity");
+                });
+                ui.weak("Jet clustering uses all particles.");
+            });
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct DetectorGeometryWin {
+    pub(crate) is_open: bool,
+}
+
+impl DetectorGeometryWin {
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        detector: &mut plotter::DetectorGeometry,
+    ) {
+        egui::Window::new("Detector geometry")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut detector.enabled, "Show detector");
+                ui.add_enabled_ui(detector.enabled, |ui| {
+                    let mut removed = None;
+                    for (n, layer) in detector.layers.iter_mut().enumerate() {
+                        ui.push_id(n, |ui| {
+                            ui.separator();
+                            ui.horizontal(|ui| {
+                                ui.text_edit_singleline(&mut layer.name);
+                                if ui.button("Remove").clicked() {
+                                    removed = Some(n);
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_srgba(&mut layer.colour);
+                                ui.label("Colour");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    DragValue::new(&mut layer.radius)
+                                        .speed(0.01)
+                                        .clamp_range(0.0..=1.0),
+                                );
+                                ui.label("Radius");
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    DragValue::new(&mut layer.half_length)
+                                        .speed(0.01)
+                                        .clamp_range(0.0..=1.0),
+                                );
+                                ui.label("Half length");
+                            });
+                        });
+                    }
+                    if let Some(n) = removed {
+                        detector.layers.remove(n);
+                    }
+                    ui.separator();
+                    if ui.button("Add layer").clicked() {
+                        detector.layers.push(plotter::DetectorLayer {
+                            name: format!(
+                                "Layer {}",
+                                detector.layers.len() + 1
+                            ),
+                            radius: 0.5,
+                            half_length: 0.5,
+                            colour: egui::Color32::GRAY,
+                        });
+                    }
+                });
+            });
+    }
+}
+
+/// Transverse slice through the detector
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct DetectorWin {
+    pub(crate) is_open: bool,
+    #[serde(skip)]
+    texture: Option<egui::TextureHandle>,
+    #[serde(skip)]
+    pt_max: f64,
+    // event index, jets, and settings the cached texture was drawn for
+    #[serde(skip)]
+    cache_key: Option<DetectorViewKey>,
+}
+
+type DetectorViewKey = (
+    usize,
+    Vec<PseudoJet>,
+    plotter::Settings,
+    plotter::DetectorGeometry,
+);
+
+const DETECTOR_VIEW_SIZE: [usize; 2] = [400, 400];
+
+impl DetectorWin {
+    /// Forget the view drawn for the previously shown events
+    pub(crate) fn clear_cache(&mut self) {
+        self.cache_key = None;
+    }
+
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        plotter: &mut Plotter,
+        event_idx: usize,
+        event: &Event,
+        jets: &[PseudoJet],
+    ) {
+        if !self.is_open {
+            return;
+        }
+        let cache_key = Some((
+            event_idx,
+            jets.to_vec(),
+            plotter.settings.clone(),
+            plotter.settings_3d.detector.clone(),
+        ));
+        if cache_key != self.cache_key {
+            self.draw(ctx, plotter, event, jets);
+            self.cache_key = cache_key;
+        }
+        let Some(texture) = &self.texture else {
+            return;
+        };
+        let pt_max = self.pt_max;
+        egui::Window::new("Transverse view")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                let img = egui::load::SizedTexture::from_handle(texture);
+                ui.add(egui::Image::from_texture(img));
+                let detector = &mut plotter.settings_3d.detector;
+                ui.horizontal(|ui| {
+                    ui.label("Magnetic field");
+                    ui.add(
+                        DragValue::new(&mut detector.field_strength)
+                            .clamp_range(0.0..=10.0)
+                            .speed(0.05)
+                            .suffix(" T"),
+                    );
+                });
+                if pt_max > 0. {
+                    let tracks = if detector.field_strength > 0. {
+                        "Neutral track length"
+                    } else {
+                        "Track length"
+                    };
+                    ui.weak(format!(
+                        "{tracks} ∝ pT, full radius: {pt_max:.1} GeV"
+                    ));
+                }
+            });
+    }
+
+    fn draw(
+        &mut self,
+        ctx: &Context,
+        plotter: &mut Plotter,
+        event: &Event,
+        jets: &[PseudoJet],
+    ) {
+        let [width, height] = DETECTOR_VIEW_SIZE;
+        let mut img = vec![0u8; width * height * BYTES_PER_RGBA_PIXEL];
+        self.pt_max = plotter
+            .plot_transverse(event, jets, &mut img, DETECTOR_VIEW_SIZE)
+            .unwrap_or_else(|err| {
+                error!("Failed to draw transverse view: {err}");
+                0.
+            });
+        rgb_to_rgba(&mut img);
+        let img =
+            egui::ColorImage::from_rgba_premultiplied(DETECTOR_VIEW_SIZE, &img);
+        match &mut self.texture {
+            Some(texture) => texture.set(img, egui::TextureOptions::default()),
+            None => {
+                self.texture = Some(ctx.load_texture(
+                    "transverse view",
+                    img,
+                    egui::TextureOptions::default(),
+                ))
+            }
+        }
+    }
+}
+
+/// Actions for a selected jet
+#[derive(Default)]
+pub(crate) struct JetSelectionWin {
+    jet: Option<PseudoJet>,
+    colour: egui::Color32,
+    pos: Option<Pos2>,
+}
+
+impl JetSelectionWin {
+    pub(crate) fn select(&mut self, jet: PseudoJet, pos: Option<Pos2>) {
+        self.jet = Some(jet);
+        self.pos = pos;
+        if self.colour == egui::Color32::default() {
+            self.colour = egui::Color32::RED;
+        }
+    }
+
+    pub(crate) fn show(&mut self, ctx: &Context, plotter: &mut Plotter) {
+        let Some(jet) = self.jet else {
+            return;
+        };
+        let mut is_open = true;
+        let mut win = egui::Window::new("Selected jet")
+            .open(&mut is_open)
+            .title_bar(true);
+        if let Some(pos) = self.pos.take() {
+            win = win.current_pos(pos);
+        }
+        let mut clear = false;
+        win.show(ctx, |ui| {
+            ui.label(format!(
+                "pT = {:.2}, y = {:.2}, φ = {:.2}",
+                f64::from(jet.pt()),
+                f64::from(jet.rap()),
+                delta_phi(jet.phi().into(), 0.)
+            ));
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgba(&mut self.colour);
+                if ui.button("Colour this jet and constituents").clicked() {
+                    let constituents = plotter
+                        .constituents
+                        .iter()
+                        .find(|(j, _)| *j == jet)
+                        .map(|(_, constituents)| constituents.clone())
+                        .unwrap_or_default();
+                    plotter.highlight = Some(JetHighlight {
+                        jet,
+                        constituents,
+                        colour: self.colour,
+                    });
+                }
+            });
+            if let Some(highlight) = &mut plotter.highlight {
+                highlight.colour = self.colour;
+            }
+            clear = ui.button("Clear selection").clicked();
+        });
+        if clear || !is_open {
+            self.jet = None;
+            plotter.highlight = None;
+        }
+    }
+}
+
+/// Table of the clustered jets
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct JetListWin {
+    pub(crate) is_open: bool,
+    /// Substructure of each jet in `counted_for`
+    #[serde(skip)]
+    substructure: Vec<Option<JetSubstructure>>,
+    #[serde(skip)]
+    counted_for: Vec<PseudoJet>,
+}
+
+/// Properties of a jet that depend on its constituents
+struct JetSubstructure {
+    nconstituents: usize,
+    /// N-subjettiness τ_1, τ_2, τ_3
+    tau: [f64; 3],
+}
+
+impl JetSubstructure {
+    fn new(constituents: &[Particle], radius: f64) -> Self {
+        let constituents =
+            Vec::from_iter(constituents.iter().map(|p| PseudoJet::from(p.p)));
+        Self {
+            nconstituents: constituents.len(),
+            tau: [1, 2, 3].map(|n| n_subjettiness(&constituents, n, radius)),
+        }
+    }
+}
+
+/// Ratio of N-subjettiness values, undefined if the denominator vanishes
+fn tau_ratio(num: f64, den: f64) -> String {
+    if den > 0. {
+        format!("{:.3}", num / den)
+    } else {
+        "—".to_owned()
+    }
+}
+
+impl JetListWin {
+    /// Show the `jets`, with the constituents found during clustering
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        jets: &[PseudoJet],
+        constituents: &[(PseudoJet, Vec<Particle>)],
+        jet_def: &JetDefinition,
+    ) {
+        if !self.is_open {
+            return;
+        }
+        if self.counted_for != jets {
+            self.substructure = Vec::from_iter(jets.iter().map(|jet| {
+                constituents
+                    .iter()
+                    .find(|(j, _)| j == jet)
+                    .map(|(_, c)| JetSubstructure::new(c, jet_def.radius))
+            }));
+            self.counted_for = jets.to_vec();
+        }
+        egui::Window::new("Jets")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                if jets.is_empty() {
+                    ui.label("No jets");
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("jet list grid").striped(true).show(
+                        ui,
+                        |ui| {
+                            let headings = [
+                                "pT",
+                                "y",
+                                "φ",
+                                "m",
+                                "constituents",
+                                "τ2/τ1",
+                                "τ3/τ2",
+                            ];
+                            for heading in headings {
+                                ui.strong(heading);
+                            }
+                            ui.end_row();
+                            for (jet, sub) in
+                                jets.iter().zip(&self.substructure)
+                            {
+                                let m = jet_mass(jet);
+                                ui.label(format!("{:.2}", f64::from(jet.pt())));
+                                ui.label(format!(
+                                    "{:.2}",
+                                    f64::from(jet.rap())
+                                ));
+                                ui.label(format!(
+                                    "{:.2}",
+                                    delta_phi(jet.phi().into(), 0.)
+                                ));
+                                ui.label(format!("{m:.2}"));
+                                if let Some(sub) = sub {
+                                    let [tau1, tau2, tau3] = sub.tau;
+                                    ui.label(sub.nconstituents.to_string());
+                                    ui.label(tau_ratio(tau2, tau1));
+                                    ui.label(tau_ratio(tau3, tau2));
+                                } else {
+                                    for _ in 0..3 {
+                                        ui.label("?");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        },
+                    );
+                });
+            });
+    }
+}
+
+// TODO: choice for jets
+#[derive(Default, Deserialize, Serialize)]
 pub(crate) struct ParticleStyleChoiceWin {
     pub(crate) is_open: bool,
-    pub(crate) id: ParticleID,
+    /// Species whose style is edited together
+    ids: Vec<ParticleID>,
     pos: Option<Pos2>,
 }
 
@@ -110,12 +1340,12 @@ impl ParticleStyleChoiceWin {
         ctx: &Context,
         settings: &mut plotter::Settings,
     ) {
-        let name = self.id.name().or(self.id.symbol());
-        let title = if let Some(name) = name {
-            format!("Plot style for {name}")
-        } else {
-            format!("Plot style for particle id {}", self.id.id())
+        let Some(&first) = self.ids.first() else {
+            return;
         };
+        let names =
+            Vec::from_iter(self.ids.iter().map(|&id| particle_name(id)));
+        let title = format!("Plot style for {}", names.join(", "));
         let mut is_open = self.is_open;
         let mut win =
             egui::Window::new(title).open(&mut is_open).title_bar(true);
@@ -123,7 +1353,11 @@ impl ParticleStyleChoiceWin {
             win = win.current_pos(pos);
         }
         win.show(ctx, |ui| {
-            let style = settings.get_particle_style_mut(self.id);
+            let palette = settings.palette;
+            // edit the style of the first species and apply each changed
+            // property to all selected species
+            let old = settings.particle_style(first);
+            let mut style = old;
             ui.horizontal(|ui| {
                 ui.color_edit_button_srgba(&mut style.colour);
                 ui.label("Marker colour");
@@ -146,22 +1380,99 @@ impl ParticleStyleChoiceWin {
                 ui.add(DragValue::new(&mut style.size));
                 ui.label("Marker size");
             });
+            ui.horizontal(|ui| {
+                ui.color_edit_button_srgba(&mut style.outline);
+                ui.add(
+                    DragValue::new(&mut style.outline_width)
+                        .clamp_range(0.0..=10.0)
+                        .speed(0.1),
+                );
+                ui.label("Outline colour and width");
+            });
+            let reset = ui.button("Reset to default").clicked();
+            if reset || style != old {
+                for &id in &self.ids {
+                    let target = settings.get_particle_style_mut(id);
+                    if reset {
+                        *target = ParticleStyle::default_for(id, palette);
+                    } else {
+                        apply_style_changes(target, &old, &style);
+                    }
+                }
+            }
+            let mut min_pt =
+                settings.min_pt.get(&first).copied().unwrap_or_default();
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    DragValue::new(&mut min_pt).clamp_range(0.0..=f64::MAX),
+                );
+                ui.label("Minimum transverse momentum");
+                if response.changed() {
+                    for &id in &self.ids {
+                        if min_pt > 0. {
+                            settings.min_pt.insert(id, min_pt);
+                        } else {
+                            settings.min_pt.remove(&id);
+                        }
+                    }
+                }
+            });
+            if self.ids.len() == 1 {
+                ui.weak("Ctrl-click further particles to style them together");
+            }
         });
         self.is_open = is_open;
     }
 
+    /// Edit the style of the given species
+    ///
+    /// With `extend`, the species are added to the species already being
+    /// edited, or removed if they are already part of the selection.
+    pub(crate) fn select(
+        &mut self,
+        ids: impl IntoIterator<Item = ParticleID>,
+        extend: bool,
+    ) {
+        if !extend || !self.is_open {
+            self.ids.clear();
+        }
+        for id in ids {
+            match self.ids.iter().position(|&i| i == id) {
+                Some(pos) if extend => {
+                    self.ids.remove(pos);
+                }
+                Some(_) => {}
+                None => self.ids.push(id),
+            }
+        }
+        self.is_open = !self.ids.is_empty();
+    }
+
     pub(crate) fn set_pos(&mut self, pos: Option<Pos2>) {
         self.pos = pos;
     }
 }
 
-impl Default for ParticleStyleChoiceWin {
-    fn default() -> Self {
-        Self {
-            is_open: false,
-            id: ParticleID::new(0),
-            pos: None,
-        }
+/// Copy the properties that differ between `old` and `new` to `target`
+fn apply_style_changes(
+    target: &mut ParticleStyle,
+    old: &ParticleStyle,
+    new: &ParticleStyle,
+) {
+    if new.colour != old.colour {
+        target.colour = new.colour;
+    }
+    if new.shape != old.shape {
+        target.shape = new.shape;
+    }
+    if new.size != old.size {
+        target.size = new.size;
+    }
+    if new.outline != old.outline {
+        target.outline = new.outline;
+    }
+    if new.outline_width != old.outline_width {
+        target.outline_width = new.outline_width;
     }
 }
 
@@ -170,6 +1481,7 @@ pub struct ExportDialogue {
     pub format: ExportFormat,
     pub kind: PlotKind,
     pub event_id: usize,
+    pub(crate) options: ExportOptions,
     dialogue: egui_file::FileDialog,
 }
 
@@ -179,6 +1491,7 @@ impl Default for ExportDialogue {
             format: ExportFormat::Asymptote, // some default, doesn't matter which
             kind: PlotKind::YLogPt,
             event_id: Default::default(),
+            options: Default::default(),
             dialogue: egui_file::FileDialog::save_file(None)
                 .title("Export event"),
         }
@@ -186,12 +1499,62 @@ impl Default for ExportDialogue {
 }
 
 impl ExportDialogue {
-    pub(crate) fn show(&mut self, ctx: &Context) -> Option<&Path> {
+    pub(crate) fn show(&mut self, ctx: &Context) -> Option<PathBuf> {
         self.dialogue.show(ctx);
-        if self.dialogue.selected() {
-            self.dialogue.path()
+        let has_options = matches!(
+            self.format,
+            ExportFormat::Asymptote
+                | ExportFormat::Matplotlib
+                | ExportFormat::Pdf
+                | ExportFormat::Png
+        );
+        if self.dialogue.visible() && has_options {
+            egui::Window::new("Export options").title_bar(true).show(
+                ctx,
+                |ui| match self.format {
+                    ExportFormat::Asymptote => {
+                        ui.checkbox(
+                            &mut self.options.standalone_latex,
+                            "Standalone LaTeX document",
+                        );
+                        ui.checkbox(
+                            &mut self.options.provenance,
+                            "Document event and plot ranges",
+                        );
+                        figure_size_ui(ui, &mut self.options.size, false);
+                    }
+                    ExportFormat::Matplotlib => {
+                        ui.checkbox(
+                            &mut self.options.provenance,
+                            "Document event and plot ranges",
+                        );
+                        figure_size_ui(ui, &mut self.options.size, true);
+                    }
+                    ExportFormat::Csv | ExportFormat::Json => {}
+                    ExportFormat::Pdf => {
+                        figure_size_ui(ui, &mut self.options.size, false);
+                    }
+                    ExportFormat::Png => {
+                        figure_size_ui(ui, &mut self.options.size, true);
+                        let [width, height] = self.options.size.pixels();
+                        ui.label(format!("{width} × {height} pixels"));
+                    }
+                },
+            );
+        }
+        if !self.dialogue.selected() {
+            return None;
+        }
+        let path = self.dialogue.path()?;
+        let is_default_suffix = path
+            .extension()
+            .is_some_and(|ext| ext == self.format.suffix());
+        let standalone = self.format == ExportFormat::Asymptote
+            && self.options.standalone_latex;
+        if standalone && is_default_suffix {
+            Some(path.with_extension("tex"))
         } else {
-            None
+            Some(path.to_owned())
         }
     }
 
@@ -208,6 +1571,187 @@ impl ExportDialogue {
     }
 }
 
+/// Controls for the physical size of exported figures and, if `with_dpi`
+/// is set, their resolution
+fn figure_size_ui(ui: &mut egui::Ui, size: &mut FigureSize, with_dpi: bool) {
+    let [width, height] = &mut size.mm;
+    ui.horizontal(|ui| {
+        ui.label("Size");
+        ui.add(
+            DragValue::new(width)
+                .clamp_range(10.0..=1000.0)
+                .suffix(" mm"),
+        );
+        ui.label("×");
+        ui.add(
+            DragValue::new(height)
+                .clamp_range(10.0..=1000.0)
+                .suffix(" mm"),
+        );
+    });
+    if with_dpi {
+        ui.horizontal(|ui| {
+            ui.label("Resolution");
+            ui.add(
+                DragValue::new(&mut size.dpi)
+                    .clamp_range(10..=2400)
+                    .suffix(" dpi"),
+            );
+        });
+    }
+}
+
+/// Dialogue for exporting all loaded events into a directory
+#[derive(Debug)]
+pub struct BatchExportDialogue {
+    pub(crate) is_open: bool,
+    pub(crate) format: ExportFormat,
+    pub(crate) kind: PlotKind,
+    dir: Option<PathBuf>,
+    dialogue: egui_file::FileDialog,
+}
+
+impl Default for BatchExportDialogue {
+    fn default() -> Self {
+        Self {
+            is_open: false,
+            format: ExportFormat::Asymptote,
+            kind: PlotKind::YPhi,
+            dir: None,
+            dialogue: egui_file::FileDialog::select_folder(None)
+                .title("Export directory"),
+        }
+    }
+}
+
+impl BatchExportDialogue {
+    /// Show the dialogue, returning the target directory when the
+    /// user starts the export
+    ///
+    /// `cancel` is the cancellation flag of a running export.
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+        cancel: Option<&AtomicBool>,
+    ) -> Option<PathBuf> {
+        self.dialogue.show(ctx);
+        if self.dialogue.selected() {
+            self.dir = self.dialogue.path().map(|p| p.to_owned());
+        }
+        let mut start = false;
+        egui::Window::new("Export all events")
+            .open(&mut self.is_open)
+            .title_bar(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("batch export grid").show(ui, |ui| {
+                    ui.label("Plot");
+                    egui::ComboBox::from_id_source("batch export kind")
+                        .selected_text(self.kind.to_string())
+                        .show_ui(ui, |ui| {
+                            for kind in PlotKind::iter() {
+                                let name = kind.to_string();
+                                ui.selectable_value(&mut self.kind, kind, name);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Format");
+                    egui::ComboBox::from_id_source("batch export format")
+                        .selected_text(self.format.to_string())
+                        .show_ui(ui, |ui| {
+                            for format in ExportFormat::iter() {
+                                let name = format.to_string();
+                                ui.selectable_value(
+                                    &mut self.format,
+                                    format,
+                                    name,
+                                );
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Directory");
+                    ui.horizontal(|ui| {
+                        if let Some(dir) = &self.dir {
+                            ui.label(dir.display().to_string());
+                        }
+                        if ui.button("Choose…").clicked() {
+                            self.dialogue.open();
+                        }
+                    });
+                    ui.end_row();
+                });
+                match cancel {
+                    Some(cancel) => {
+                        if ui.button("Cancel").clicked() {
+                            cancel.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    None => {
+                        let export = ui.add_enabled(
+                            self.dir.is_some(),
+                            egui::Button::new("Export"),
+                        );
+                        start = export.clicked();
+                    }
+                }
+            });
+        if start {
+            self.dir.clone()
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a style preset is saved or loaded
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PresetAction {
+    Save,
+    Load,
+}
+
+#[derive(Debug)]
+pub(crate) struct PresetDialogue {
+    action: PresetAction,
+    dialogue: egui_file::FileDialog,
+}
+
+impl Default for PresetDialogue {
+    fn default() -> Self {
+        Self {
+            action: PresetAction::Load,
+            dialogue: egui_file::FileDialog::open_file(None)
+                .title("Load style preset"),
+        }
+    }
+}
+
+impl PresetDialogue {
+    pub(crate) fn show(
+        &mut self,
+        ctx: &Context,
+    ) -> Option<(PresetAction, PathBuf)> {
+        self.dialogue.show(ctx);
+        if self.dialogue.selected() {
+            let path = self.dialogue.path()?.to_owned();
+            Some((self.action, path))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn open(&mut self, action: PresetAction) {
+        self.action = action;
+        self.dialogue = match action {
+            PresetAction::Save => egui_file::FileDialog::save_file(None)
+                .title("Save style preset")
+                .default_filename("preset.json"),
+            PresetAction::Load => egui_file::FileDialog::open_file(None)
+                .title("Load style preset"),
+        };
+        self.dialogue.open();
+    }
+}
+
 #[derive(Debug)]
 pub struct ImportDialogue {
     dialogue: egui_file::FileDialog,
@@ -238,3 +1782,10 @@ impl ImportDialogue {
         self.dialogue.open();
     }
 }
+
+/// Invariant mass of a jet, zero for unphysical four-momenta
+fn jet_mass(jet: &PseudoJet) -> f64 {
+    let [e, px, py, pz] =
+        [jet.e(), jet.px(), jet.py(), jet.pz()].map(f64::from);
+    (e * e - px * px - py * py - pz * pz).max(0.).sqrt()
+}